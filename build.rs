@@ -0,0 +1,361 @@
+//! Generates the LuaLS annotations of the tables that the library exchanges with Lua, from the
+//! serde types of the Rust client and configuration. Only the builds of the Neovim module need
+//! them, so nothing is generated without the `lua` feature.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "lua")]
+    annotations::generate();
+}
+
+#[cfg(feature = "lua")]
+mod annotations {
+    use std::collections::HashSet;
+    use std::fmt::Write;
+
+    use syn::{Attribute, Fields, GenericArgument, Item, PathArguments, Type};
+
+    static SOURCES: &[&str] = &["src/client.rs", "src/config.rs"];
+    static OUTPUT: &str = "lua/youtrack/types.lua";
+    static NAMESPACE: &str = "youtrack.lib";
+
+    pub fn generate() {
+        let files: Vec<syn::File> = SOURCES
+            .iter()
+            .map(|path| {
+                println!("cargo:rerun-if-changed={}", path);
+
+                let content = std::fs::read_to_string(path)
+                    .unwrap_or_else(|err| panic!("Can not read {}: {}", path, err));
+
+                syn::parse_file(&content)
+                    .unwrap_or_else(|err| panic!("Can not parse {}: {}", path, err))
+            })
+            .collect();
+
+        let items: Vec<&Item> = files
+            .iter()
+            .flat_map(|file| file.items.iter())
+            .filter(|item| is_serde(item))
+            .collect();
+        let names: HashSet<String> = items.iter().filter_map(|item| name(item)).collect();
+
+        let mut output = format!(
+            "---@meta\n-- Generated by build.rs from {}, do not edit.\n",
+            SOURCES.join(" and ")
+        );
+
+        items.iter().for_each(|item| {
+            output.push('\n');
+            output.push_str(&annotate(item, &names));
+        });
+
+        let generated =
+            std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("types.lua");
+        std::fs::write(&generated, &output)
+            .unwrap_or_else(|err| panic!("Can not write {}: {}", generated.display(), err));
+
+        // only written on changes, so that the build does not touch the tree otherwise, and a
+        // read-only checkout like a vendored source keeps the annotations that it ships with
+        if std::fs::read_to_string(OUTPUT).ok().as_deref() != Some(output.as_str()) {
+            if let Err(err) = std::fs::write(OUTPUT, output) {
+                println!(
+                    "cargo:warning=Can not update {}, the generated annotations are in {}: {}",
+                    OUTPUT,
+                    generated.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Public structs and enums that derive one of the serde traits.
+    fn is_serde(item: &Item) -> bool {
+        let (vis, attrs) = match item {
+            Item::Struct(item) => (&item.vis, &item.attrs),
+            Item::Enum(item) => (&item.vis, &item.attrs),
+            _ => return false,
+        };
+
+        matches!(vis, syn::Visibility::Public(_))
+            && attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("derive"))
+                .any(|attr| {
+                    let derive = attr.meta.require_list().map(|list| list.tokens.to_string());
+
+                    derive.is_ok_and(|derive| {
+                        derive.contains("Serialize") || derive.contains("Deserialize")
+                    })
+                })
+    }
+
+    fn name(item: &Item) -> Option<String> {
+        match item {
+            Item::Struct(item) => Some(item.ident.to_string()),
+            Item::Enum(item) => Some(item.ident.to_string()),
+            _ => None,
+        }
+    }
+
+    fn annotate(item: &Item, names: &HashSet<String>) -> String {
+        let mut output = String::new();
+
+        match item {
+            Item::Struct(item) => {
+                comment(&mut output, &item.attrs);
+                writeln!(output, "---@class {}.{}", NAMESPACE, item.ident).unwrap();
+
+                let container = serde(&item.attrs);
+
+                if let Fields::Named(ref fields) = item.fields {
+                    fields.named.iter().for_each(|field| {
+                        let attrs = serde(&field.attrs);
+
+                        if attrs.skip {
+                            return;
+                        }
+
+                        let (ty, optional) = lua_type(&field.ty, names);
+                        let name = attrs.rename.unwrap_or_else(|| {
+                            container.rename(&field.ident.as_ref().unwrap().to_string())
+                        });
+                        let optional = optional || attrs.default || container.default;
+
+                        write!(
+                            output,
+                            "---@field {}{} {}",
+                            name,
+                            if optional { "?" } else { "" },
+                            ty
+                        )
+                        .unwrap();
+
+                        match doc(&field.attrs) {
+                            Some(doc) => writeln!(output, " {}", doc).unwrap(),
+                            None => output.push('\n'),
+                        }
+                    });
+                }
+            }
+            Item::Enum(item) => {
+                comment(&mut output, &item.attrs);
+                writeln!(output, "---@alias {}.{}", NAMESPACE, item.ident).unwrap();
+
+                let container = serde(&item.attrs);
+
+                item.variants.iter().for_each(|variant| {
+                    let name = serde(&variant.attrs)
+                        .rename
+                        .unwrap_or_else(|| container.rename(&variant.ident.to_string()));
+                    let ty = match (&variant.fields, container.untagged) {
+                        (Fields::Unit, _) => format!("\"{}\"", name),
+                        (Fields::Unnamed(fields), true) => lua_type(&fields.unnamed[0].ty, names).0,
+                        (Fields::Named(fields), true) => table(fields, names),
+                        (Fields::Unnamed(fields), false) => {
+                            format!(
+                                "{{ {}: {} }}",
+                                name,
+                                lua_type(&fields.unnamed[0].ty, names).0
+                            )
+                        }
+                        (Fields::Named(fields), false) => {
+                            format!("{{ {}: {} }}", name, table(fields, names))
+                        }
+                    };
+
+                    write!(output, "---| {}", ty).unwrap();
+
+                    match doc(&variant.attrs) {
+                        Some(doc) => writeln!(output, " # {}", doc).unwrap(),
+                        None => output.push('\n'),
+                    }
+                });
+            }
+            _ => {}
+        }
+
+        output
+    }
+
+    /// Inline table of the named fields of an enum variant.
+    fn table(fields: &syn::FieldsNamed, names: &HashSet<String>) -> String {
+        let fields: Vec<String> = fields
+            .named
+            .iter()
+            .map(|field| {
+                let (ty, optional) = lua_type(&field.ty, names);
+
+                format!(
+                    "{}{}: {}",
+                    field.ident.as_ref().unwrap(),
+                    if optional { "?" } else { "" },
+                    ty
+                )
+            })
+            .collect();
+
+        format!("{{ {} }}", fields.join(", "))
+    }
+
+    /// Returns the Lua type of the Rust type, and whether it can be left out.
+    fn lua_type(ty: &Type, names: &HashSet<String>) -> (String, bool) {
+        let path = match ty {
+            Type::Reference(reference) => return lua_type(&reference.elem, names),
+            Type::Path(path) => &path.path,
+            _ => return ("any".to_string(), false),
+        };
+        let Some(segment) = path.segments.last() else {
+            return ("any".to_string(), false);
+        };
+
+        let args: Vec<&Type> = match segment.arguments {
+            PathArguments::AngleBracketed(ref args) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        let arg = |i: usize| {
+            args.get(i)
+                .map(|ty| lua_type(ty, names).0)
+                .unwrap_or_else(|| "any".to_string())
+        };
+
+        let ty = match segment.ident.to_string().as_str() {
+            "Option" => return (arg(0), true),
+            "Box" | "Arc" | "Rc" => return (arg(0), false),
+            "String" | "str" | "char" | "Url" => "string".to_string(),
+            "bool" => "boolean".to_string(),
+            "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+            | "u128" | "usize" | "f32" | "f64" => "number".to_string(),
+            "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => {
+                let item = arg(0);
+
+                if item.contains(' ') {
+                    format!("({})[]", item)
+                } else {
+                    format!("{}[]", item)
+                }
+            }
+            "HashMap" | "BTreeMap" => format!("table<{}, {}>", arg(0), arg(1)),
+            name if names.contains(name) => format!("{}.{}", NAMESPACE, name),
+            _ => "any".to_string(),
+        };
+
+        (ty, false)
+    }
+
+    /// Writes the doc comment of the item as a Lua comment.
+    fn comment(output: &mut String, attrs: &[Attribute]) {
+        if let Some(doc) = doc(attrs) {
+            writeln!(output, "--- {}", doc).unwrap();
+        }
+    }
+
+    /// Doc comment of the item, joined into a single line.
+    fn doc(attrs: &[Attribute]) -> Option<String> {
+        let lines: Vec<String> = attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .filter_map(|attr| match attr.meta.require_name_value().ok()?.value {
+                syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(ref lit),
+                    ..
+                }) => Some(lit.value().trim().to_string()),
+                _ => None,
+            })
+            .collect();
+
+        Some(lines.join(" ")).filter(|doc| !doc.is_empty())
+    }
+
+    /// The serde attributes that change the shape of the table.
+    #[derive(Default)]
+    struct Serde {
+        rename: Option<String>,
+        rename_all: Option<String>,
+        default: bool,
+        skip: bool,
+        untagged: bool,
+    }
+
+    impl Serde {
+        fn rename(&self, name: &str) -> String {
+            match self.rename_all.as_deref() {
+                Some("lowercase") => name.to_lowercase(),
+                Some("UPPERCASE") => name.to_uppercase(),
+                Some("snake_case") => snake_case(name),
+                Some("camelCase") => {
+                    let mut chars = name.chars();
+
+                    chars
+                        .next()
+                        .map(|first| first.to_lowercase().chain(chars).collect())
+                        .unwrap_or_default()
+                }
+                _ => name.to_string(),
+            }
+        }
+    }
+
+    fn serde(attrs: &[Attribute]) -> Serde {
+        let mut serde = Serde::default();
+
+        attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("serde"))
+            .for_each(|attr| {
+                let _ = attr.parse_nested_meta(|meta| {
+                    let value = || -> syn::Result<String> {
+                        Ok(meta.value()?.parse::<syn::LitStr>()?.value())
+                    };
+
+                    match meta
+                        .path
+                        .get_ident()
+                        .map(|ident| ident.to_string())
+                        .as_deref()
+                    {
+                        Some("rename") => serde.rename = Some(value()?),
+                        Some("rename_all") => serde.rename_all = Some(value()?),
+                        Some("default") => {
+                            serde.default = true;
+
+                            // the default function does not matter for the shape
+                            if meta.input.peek(syn::Token![=]) {
+                                value()?;
+                            }
+                        }
+                        Some("skip") => serde.skip = true,
+                        Some("untagged") => serde.untagged = true,
+                        _ => {
+                            if meta.input.peek(syn::Token![=]) {
+                                meta.value()?.parse::<syn::Expr>()?;
+                            }
+                        }
+                    }
+
+                    Ok(())
+                });
+            });
+
+        serde
+    }
+
+    fn snake_case(name: &str) -> String {
+        name.chars()
+            .enumerate()
+            .flat_map(|(i, c)| {
+                let separator = (i > 0 && c.is_uppercase()).then_some('_');
+
+                separator.into_iter().chain(c.to_lowercase())
+            })
+            .collect()
+    }
+}
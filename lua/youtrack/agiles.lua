@@ -10,8 +10,6 @@ local config = require("youtrack.config")
 function M.get_agiles(opts)
 	opts = opts or {}
 
-	local c = config.read()
-
 	lib.get_agiles(nil, function(err, agiles)
 		if err then
 			log.p.error(err)
@@ -29,7 +27,7 @@ function M.get_agiles(opts)
 				return
 			end
 
-			vim.ui.open(("%s/agiles/%s"):format(c.url, agile.id))
+			vim.ui.open(("%s/agiles/%s"):format(config.url(), agile.id))
 		end)
 	end)
 end
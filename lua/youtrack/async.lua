@@ -0,0 +1,160 @@
+local M = {}
+
+local lib = require("youtrack.lib")
+
+---@class youtrack.Awaitable
+---@field await fun(self: youtrack.Awaitable): any, any Suspends the running coroutine until the result arrives, raises the error.
+---@field result fun(self: youtrack.Awaitable): any, any, any Error, result and meta when it is settled.
+---@field is_settled fun(self: youtrack.Awaitable): boolean
+---@field on_done fun(self: youtrack.Awaitable, listener: fun())
+---@field cancel fun(self: youtrack.Awaitable)
+
+---@class youtrack.Combined: youtrack.Awaitable
+---@field private awaitables youtrack.Awaitable[]
+---@field private values? any[]
+---@field private listeners fun()[]
+local Combined = {}
+Combined.__index = Combined
+
+---@param awaitables youtrack.Awaitable[]
+---@return youtrack.Combined
+function Combined.new(awaitables)
+	return setmetatable({ awaitables = awaitables, listeners = {} }, Combined)
+end
+
+function Combined:is_settled()
+	return self.values ~= nil
+end
+
+function Combined:result()
+	if self.values then
+		return unpack(self.values, 1, 3)
+	end
+end
+
+function Combined:on_done(listener)
+	if self.values then
+		listener()
+	else
+		table.insert(self.listeners, listener)
+	end
+end
+
+function Combined:cancel()
+	for _, awaitable in ipairs(self.awaitables) do
+		awaitable:cancel()
+	end
+end
+
+---@param err any
+---@param res any
+function Combined:settle(err, res)
+	if self.values then
+		return
+	end
+
+	self.values = { err, res }
+
+	for _, listener in ipairs(self.listeners) do
+		listener()
+	end
+
+	self.listeners = {}
+end
+
+Combined.await = lib.await
+
+--- Runs the function in a new coroutine, where the results can be awaited.
+---@param fn fun(...)
+function M.run(fn, ...)
+	local co = coroutine.create(fn)
+	local ok, err = coroutine.resume(co, ...)
+
+	if not ok then
+		error(err, 0)
+	end
+end
+
+--- Settles with the list of results once all of them succeeded, or with the first error.
+---@param awaitables youtrack.Awaitable[]
+---@return youtrack.Awaitable
+function M.all(awaitables)
+	local combined = Combined.new(awaitables)
+	local results = {}
+	local remaining = #awaitables
+
+	if remaining == 0 then
+		combined:settle(nil, results)
+	end
+
+	for i, awaitable in ipairs(awaitables) do
+		awaitable:on_done(function()
+			local err, res = awaitable:result()
+
+			if err ~= nil then
+				combined:settle(err, nil)
+
+				return
+			end
+
+			results[i] = res
+			remaining = remaining - 1
+
+			if remaining == 0 then
+				combined:settle(nil, results)
+			end
+		end)
+	end
+
+	return combined
+end
+
+--- Settles with the list of errors and results once all of them are settled, it never fails.
+---@param awaitables youtrack.Awaitable[]
+---@return youtrack.Awaitable
+function M.all_settled(awaitables)
+	local combined = Combined.new(awaitables)
+	local results = {}
+	local remaining = #awaitables
+
+	if remaining == 0 then
+		combined:settle(nil, results)
+	end
+
+	for i, awaitable in ipairs(awaitables) do
+		awaitable:on_done(function()
+			local err, res = awaitable:result()
+
+			results[i] = { err = err, result = res }
+			remaining = remaining - 1
+
+			if remaining == 0 then
+				combined:settle(nil, results)
+			end
+		end)
+	end
+
+	return combined
+end
+
+--- Settles with the first one that is settled, and cancels the others.
+---@param awaitables youtrack.Awaitable[]
+---@return youtrack.Awaitable
+function M.race(awaitables)
+	local combined = Combined.new(awaitables)
+
+	for _, awaitable in ipairs(awaitables) do
+		awaitable:on_done(function()
+			if combined:is_settled() then
+				return
+			end
+
+			combined:settle(awaitable:result())
+			combined:cancel()
+		end)
+	end
+
+	return combined
+end
+
+return M
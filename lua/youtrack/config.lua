@@ -2,14 +2,27 @@ local M = {}
 
 ---@class youtrack.Config
 ---@field log_level? number
+---@field log_targets? table<string, string> Log levels per target prefix of the library, like `youtrack_nvim::request = "trace"`.
+---@field log_file? youtrack.ConfigLogFile Writes the records of the library to a file as JSON lines.
+---@field log_redact? youtrack.ConfigLogRedact Masks secrets and personal data in the logs.
 ---@field url string
----@field token string
+---@field token string | youtrack.ConfigToken
+---@field hub_url? string Url of the Hub service, when it is not served together with Youtrack.
+---@field profile? string Name of the profile that is active after the setup.
+---@field profiles? table<string, youtrack.ConfigProfile> Additional Youtrack instances, top level configuration is the "default" profile.
 ---@field debounce? number
 ---@field ui? youtrack.ConfigUi
 ---@field queries? youtrack.Query[]
 ---@field issues? youtrack.ConfigIssues
 ---@field issue? youtrack.ConfigIssue
 ---@field create_issue? youtrack.ConfigCreateIssue
+---@field http? youtrack.ConfigHttp
+---@field retry? youtrack.ConfigRetry
+---@field cache? youtrack.ConfigCache
+---@field offline? youtrack.ConfigOffline
+---@field recorder? youtrack.ConfigRecorder
+---@field backend? youtrack.ConfigBackend
+---@field cancel_previous? string[] Library functions that cancel their previous in-flight call when called again.
 
 ---@class youtrack.ConfigUi: youtrack.ConfigUiSize
 ---@field autoclose? boolean
@@ -40,6 +53,70 @@ local M = {}
 ---@class youtrack.ConfigCreateIssue
 ---@field ui? youtrack.ConfigUiSize
 
+---@class youtrack.ConfigToken
+---@field env? string Name of the environment variable that contains the token.
+---@field file? string Path of the file that contains the token.
+---@field command? string Shell command that prints the token to stdout.
+
+---@class youtrack.ConfigProfile
+---@field url string
+---@field token string | youtrack.ConfigToken
+---@field hub_url? string
+---@field issues? youtrack.ConfigIssues
+---@field issue? youtrack.ConfigIssue
+
+---@class youtrack.ConfigHttp
+---@field connect_timeout? number Timeout in milliseconds for establishing the connection.
+---@field timeout? number Timeout in milliseconds for the whole request.
+---@field proxy? string Proxy url for both HTTP and HTTPS requests.
+---@field no_proxy? string[] Hosts that should bypass the proxy.
+---@field ca_certificates? string[] PEM files with additional root certificates.
+---@field identity? youtrack.ConfigHttpIdentity Client certificate for mutual TLS.
+---@field insecure? boolean Disables TLS certificate verification, only for local testing.
+---@field max_concurrent? number Maximum amount of requests in flight at once, others wait in order.
+
+---@class youtrack.ConfigHttpIdentity
+---@field cert string PEM file of the client certificate.
+---@field key string PEM file of the PKCS#8 private key.
+
+---@class youtrack.ConfigRetry
+---@field enabled? boolean
+---@field attempts? number Maximum amount of retries after the first attempt.
+---@field delay? number Initial delay in milliseconds.
+---@field max_delay? number Maximum delay in milliseconds for a single retry.
+---@field factor? number Multiplier for the delay on each attempt.
+
+---@class youtrack.ConfigCache
+---@field enabled? boolean
+---@field ttl? table<string, number> Time in seconds per library function that the response is fresh.
+---@field stale? number Time in seconds after the response gets stale, where it is served while revalidating.
+
+---@class youtrack.ConfigOffline
+---@field enabled? boolean
+---@field path? string Directory where the responses are persisted.
+---@field endpoints? string[] Library functions whose responses are persisted.
+---@field max_entries? number Responses that are kept per library function, where the least recently stored ones are removed.
+---@field outbox? boolean Queues the changes that can not be sent while offline to replay them later.
+---@field interval? number Interval in milliseconds for replaying the queued changes.
+
+---@class youtrack.ConfigLogFile
+---@field path? string Defaults to `youtrack/youtrack.jsonl` in the state directory, next to the offline storage.
+---@field max_size? number Size in bytes after the file is rotated.
+---@field max_files? number Amount of rotated files that are kept besides the current one.
+
+---@class youtrack.ConfigRecorder
+---@field mode? 'off' | 'record' | 'replay'
+---@field path? string HAR file of the recording.
+
+---@class youtrack.ConfigBackend
+---@field kind? 'http' | 'memory'
+---@field fixture? string JSON file with the projects, issues, saved queries and agiles of the memory backend.
+
+---@class youtrack.ConfigLogRedact
+---@field enabled? boolean Masks the authorization header and tokens, should only be disabled for local debugging.
+---@field emails? boolean
+---@field issue_text? boolean Masks summaries, descriptions and comments of issues.
+
 ---@alias youtrack.ConfigFields table<string>
 
 ---@class youtrack.Query
@@ -49,8 +126,17 @@ local M = {}
 ---@type youtrack.Config
 local defaults = {
 	log_level = vim.log.levels.INFO,
+	log_targets = {},
+	log_file = nil,
+	log_redact = {
+		enabled = true,
+		emails = false,
+		issue_text = false,
+	},
 	url = "",
 	token = "",
+	profile = nil,
+	profiles = {},
 	debounce = 1000,
 	ui = {
 		autoclose = true,
@@ -103,6 +189,49 @@ local defaults = {
 		fields = {},
 	},
 	create_issue = {},
+	http = {
+		connect_timeout = 10000,
+		timeout = 60000,
+		proxy = nil,
+		no_proxy = {},
+		ca_certificates = {},
+		identity = nil,
+		insecure = false,
+		max_concurrent = 6,
+	},
+	retry = {
+		enabled = true,
+		attempts = 3,
+		delay = 500,
+		max_delay = 10000,
+		factor = 2,
+	},
+	cancel_previous = { "get_issues" },
+	cache = {
+		enabled = true,
+		ttl = {
+			get_projects = 3600,
+			get_agiles = 3600,
+			get_saved_queries = 600,
+		},
+		stale = 86400,
+	},
+	offline = {
+		enabled = true,
+		path = vim.fn.stdpath("state") .. "/youtrack",
+		endpoints = { "get_saved_queries", "get_issues", "get_issue", "get_projects", "get_agiles" },
+		max_entries = 100,
+		outbox = true,
+		interval = 60000,
+	},
+	recorder = {
+		mode = "off",
+		path = vim.fn.stdpath("state") .. "/youtrack/recording.har",
+	},
+	backend = {
+		kind = "http",
+		fixture = nil,
+	},
 }
 
 ---@type youtrack.Config
@@ -114,12 +243,46 @@ function M.read()
 	return M.options
 end
 
+---@param token string | youtrack.ConfigToken
+---@return string | youtrack.ConfigToken
+local function expand_token(token)
+	if type(token) == "table" and token.file ~= nil then
+		return vim.tbl_extend("force", {}, token, { file = vim.fn.expand(token.file) })
+	end
+
+	return token
+end
+
 ---@param config youtrack.Config
 ---@return youtrack.Config
 function M.setup(config)
 	M.options = vim.tbl_deep_extend("force", {}, defaults, config or {})
 
+	-- links to the web interface are built by appending to the url, which can include a context path
+	M.options.url = M.options.url:gsub("/+$", "")
+	M.options.token = expand_token(M.options.token)
+	if M.options.log_file ~= nil then
+		M.options.log_file.path =
+			vim.fn.expand(M.options.log_file.path or (vim.fn.stdpath("state") .. "/youtrack/youtrack.jsonl"))
+	end
+	for _, profile in pairs(M.options.profiles) do
+		profile.url = profile.url:gsub("/+$", "")
+		profile.token = expand_token(profile.token)
+	end
+
 	return M.options
 end
 
+---Returns the url of the active profile.
+---@return string
+function M.url()
+	local profile = M.options.profiles[M.options.profile or "default"]
+
+	if profile ~= nil then
+		return profile.url
+	end
+
+	return M.options.url
+end
+
 return M
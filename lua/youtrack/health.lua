@@ -0,0 +1,112 @@
+local M = {}
+
+local config = require("youtrack.config")
+
+---@class youtrack.Connection
+---@field profile string
+---@field url string
+---@field user { id: string, login: string, name: string, email?: string }
+---@field version? string
+---@field build? string
+---@field features { time_tracking?: boolean, knowledge_base?: boolean, helpdesk?: boolean }
+
+---@class youtrack.CheckConnectionOptions
+---@field profile? string
+---@field timeout? number Time in milliseconds to wait for the result.
+
+---Checks the connection synchronously, so that it can be used in the health check.
+---@param opts? youtrack.CheckConnectionOptions
+---@return any, youtrack.Connection?
+function M.wait_connection(opts)
+	opts = opts or {}
+
+	local done = false
+	local err, res
+
+	local request = require("youtrack.lib").check_connection({ profile = opts.profile }, function(e, r)
+		err, res, done = e, r, true
+	end)
+
+	if not vim.wait(opts.timeout or 30000, function()
+		return done
+	end) then
+		request:cancel()
+
+		return "Timed out while checking the connection.", nil
+	end
+
+	return err, res
+end
+
+---@param feature boolean?
+---@return string
+local function format_feature(feature)
+	if feature == nil then
+		return "unknown"
+	end
+
+	return feature and "available" or "not available"
+end
+
+---@param res youtrack.Connection
+---@return string[]
+function M.format_connection(res)
+	return {
+		("Connected to %s [%s] as %s (%s)"):format(res.url, res.profile, res.user.name, res.user.login),
+		("Server version: %s, build: %s"):format(res.version or "unknown", res.build or "unknown"),
+		("Time tracking: %s"):format(format_feature(res.features.time_tracking)),
+		("Knowledge base: %s"):format(format_feature(res.features.knowledge_base)),
+		("Helpdesk: %s"):format(format_feature(res.features.helpdesk)),
+	}
+end
+
+---@param opts? youtrack.CheckConnectionOptions
+function M.check_connection(opts)
+	opts = opts or {}
+
+	local log = require("youtrack.log")
+
+	require("youtrack.lib").check_connection({ profile = opts.profile }, function(err, res)
+		if err then
+			log.p.error(err)
+
+			return
+		end
+
+		log.p.info(table.concat(M.format_connection(res), "\n"))
+	end)
+end
+
+function M.check()
+	vim.health.start("youtrack.nvim")
+
+	if config.read() == nil then
+		vim.health.error("Plugin has not been setup. Did you call setup?")
+
+		return
+	end
+
+	local ok, profiles = pcall(require("youtrack.lib").get_profiles)
+
+	if not ok then
+		vim.health.error(tostring(profiles))
+
+		return
+	end
+
+	for _, profile in ipairs(profiles) do
+		vim.health.start(("youtrack.nvim: %s"):format(profile.name))
+
+		local err, res = M.wait_connection({ profile = profile.name })
+
+		if err then
+			vim.health.error(tostring(err))
+		else
+			for _, line in ipairs(M.format_connection(res)) do
+				vim.health.ok(line)
+			end
+		end
+	end
+end
+
+return M
@@ -5,18 +5,141 @@ local M = {
 	get_issue = require("youtrack.issues").get_issue,
 	reset_last_issue = require("youtrack.issues").reset_lasts,
 	create_issue = require("youtrack.issues").create_issue,
+	get_profiles = require("youtrack.profiles").get_profiles,
+	set_profile = require("youtrack.profiles").set_profile,
+	check_connection = require("youtrack.health").check_connection,
+	get_outbox = require("youtrack.outbox").get_outbox,
+	retry_outbox = require("youtrack.outbox").retry_outbox,
+	discard_outbox = require("youtrack.outbox").discard_outbox,
+	invalidate_cache = function(opts)
+		return require("youtrack.lib").invalidate_cache(opts)
+	end,
+	---@param opts youtrack.RequestOptions
+	---@param callback? fun(err?: youtrack.ApiError | string, res?: any)
+	---@return youtrack.RequestHandle
+	request = function(opts, callback)
+		return require("youtrack.lib").request(opts, callback)
+	end,
+	---@param issue youtrack.lib.Issue
+	---@param profile? string
+	---@return youtrack.IssueObject
+	issue = function(issue, profile)
+		return require("youtrack.lib").issue(issue, profile)
+	end,
+	---@param project youtrack.lib.Project
+	---@param profile? string
+	---@return youtrack.ProjectObject
+	project = function(project, profile)
+		return require("youtrack.lib").project(project, profile)
+	end,
+	---@param agile youtrack.lib.Agile
+	---@param profile? string
+	---@return youtrack.AgileObject
+	agile = function(agile, profile)
+		return require("youtrack.lib").agile(agile, profile)
+	end,
+	async = require("youtrack.async"),
 	_ = {},
 }
 
+---@class youtrack.RequestHandle: youtrack.Awaitable
+---@field cancel fun(self: youtrack.RequestHandle) Cancels the request, callback will not be called.
+---@field is_done fun(self: youtrack.RequestHandle): boolean
+---@field is_cancelled fun(self: youtrack.RequestHandle): boolean
+
+---@class youtrack.IssueObject: youtrack.lib.Issue
+---@field project youtrack.ProjectObject
+---@field refresh fun(self: youtrack.IssueObject, callback?: fun(err?: youtrack.ApiError | string, res?: youtrack.IssueObject)): youtrack.RequestHandle Fetches the issue again and updates the object.
+---@field comments fun(self: youtrack.IssueObject, callback?: fun(err?: youtrack.ApiError | string, res?: youtrack.lib.Comment[])): youtrack.RequestHandle Fetches the comments, unless they are loaded already.
+---@field comment fun(self: youtrack.IssueObject, text: string, callback?: fun(err?: youtrack.ApiError | string, res?: any, meta?: any)): youtrack.RequestHandle
+---@field command fun(self: youtrack.IssueObject, query: string, callback?: fun(err?: youtrack.ApiError | string, res?: any, meta?: any)): youtrack.RequestHandle
+---@field set_field fun(self: youtrack.IssueObject, name: string, value: string, callback?: fun(err?: youtrack.ApiError | string, res?: any, meta?: any)): youtrack.RequestHandle Applies the command `<name> <value>`.
+---@field to_table fun(self: youtrack.IssueObject): youtrack.lib.Issue
+
+---@class youtrack.ProjectObject: youtrack.lib.Project
+---@field issues fun(self: youtrack.ProjectObject, opts?: youtrack.lib.GetIssues, callback?: fun(err?: youtrack.ApiError | string, res?: youtrack.IssueObject[])): youtrack.RequestHandle Searches the issues of the project.
+---@field create_issue fun(self: youtrack.ProjectObject, opts: { summary: string, description?: string }, callback?: fun(err?: youtrack.ApiError | string, res?: youtrack.IssueObject)): youtrack.RequestHandle
+---@field to_table fun(self: youtrack.ProjectObject): youtrack.lib.Project
+
+---@class youtrack.AgileObject: youtrack.lib.Agile
+---@field projects fun(self: youtrack.AgileObject): youtrack.ProjectObject[]
+---@field to_table fun(self: youtrack.AgileObject): youtrack.lib.Agile
+
+---Data of the `User` autocommands after a successful change, like `YouTrackIssueUpdated`, `YouTrackCommentAdded`, `YouTrackIssueCreated` and `YouTrackCommandApplied`.
+---@class youtrack.EventData
+---@field id string Internal id of the issue, like `2-17`.
+---@field readable_id? string Readable id of the issue, like `PROJ-42`, unless it could not be fetched.
+---@field profile string
+---@field payload table Options of the change, like `youtrack.lib.UpdateIssue`.
+---@field result any Response of the server.
+
+---@class youtrack.ResponseMeta
+---@field offline boolean Response has been served from the offline storage.
+---@field fetched_at string
+
+---@class youtrack.RequestOptions
+---@field method? string HTTP method, defaults to GET.
+---@field path string Path relative to the REST API, like `issues/PRJ-1/comments`.
+---@field query? table<string, string | number | boolean>
+---@field fields? string Fields to return, Youtrack only returns the identifiers by default.
+---@field body? table Encoded as JSON.
+---@field profile? string
+
+---@class youtrack.ApiError
+---@field message string
+---@field status number HTTP status code of the response.
+---@field code? string Error code returned by Youtrack.
+---@field description? string Error description returned by Youtrack.
+---@field workflow? string Message of the workflow rule that rejected the request.
+
+---@param level number
+---@return string
+local function log_level_name(level)
+	for name, value in pairs(vim.log.levels) do
+		if value == level then
+			return name:lower()
+		end
+	end
+
+	return "info"
+end
+
+---@param c youtrack.Config
+---@return youtrack.Config
+local function redact_tokens(c)
+	local redacted = vim.deepcopy(c)
+
+	redacted.token = "***"
+
+	for _, profile in pairs(redacted.profiles or {}) do
+		profile.token = "***"
+	end
+
+	return redacted
+end
+
 ---@param config youtrack.Config
 function M.setup(config)
 	local c = require("youtrack.config").setup(config)
 
 	local log = require("youtrack.log").setup({ level = c.log_level })
 
-	require("youtrack.lib").setup({
+	local lib = require("youtrack.lib")
+
+	lib.setup({
 		url = c.url,
 		token = c.token,
+		hub_url = c.hub_url,
+		profile = c.profile,
+		profiles = vim.tbl_map(function(profile)
+			return {
+				url = profile.url,
+				token = profile.token,
+				hub_url = profile.hub_url,
+				issues = profile.issues and { fields = profile.issues.fields },
+				issue = profile.issue and { fields = profile.issue.fields },
+			}
+		end, c.profiles),
 		queries = c.queries,
 		issues = {
 			fields = c.issues.fields,
@@ -24,9 +147,40 @@ function M.setup(config)
 		issue = {
 			fields = c.issue.fields,
 		},
+		http = c.http,
+		retry = c.retry,
+		cache = c.cache,
+		offline = {
+			enabled = c.offline.enabled,
+			path = c.offline.path,
+			endpoints = c.offline.endpoints,
+			max_entries = c.offline.max_entries,
+			outbox = c.offline.outbox,
+		},
+		recorder = c.recorder,
+		backend = c.backend,
+		cancel_previous = c.cancel_previous,
+		log = {
+			level = log_level_name(c.log_level),
+			targets = c.log_targets,
+			file = c.log_file,
+			redact = c.log_redact,
+		},
 	})
 
-	log.debug("Plugin has been setup: %s", c)
+	log.debug("Plugin has been setup: %s", c.log_redact.enabled and redact_tokens(c) or c)
+
+	lib.validate_api(nil, function(err, res)
+		if err then
+			log.p.error(err)
+
+			return
+		end
+
+		log.debug("Youtrack API has been validated: %s", res)
+	end)
+
+	require("youtrack.outbox").start()
 end
 
 return M
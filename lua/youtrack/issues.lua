@@ -41,6 +41,8 @@ function M.get_issues(opts)
 	})
 	local renderer = n.create_renderer(ui)
 	local augroup = "youtrack_issues"
+	---@type youtrack.RequestHandle?
+	local request
 
 	renderer:on_mount(function()
 		M._.renderer = renderer
@@ -57,6 +59,10 @@ function M.get_issues(opts)
 	renderer:on_unmount(function()
 		M._.renderer = nil
 
+		if request ~= nil then
+			request:cancel()
+		end
+
 		pcall(vim.api.nvim_del_augroup_by_name, augroup)
 	end)
 
@@ -188,7 +194,7 @@ function M.get_issues(opts)
 				autofocus = false,
 				border_style = c.ui.border,
 				on_press = function()
-					vim.ui.open(("%s/search/?q=%s"):format(c.url, signal_issues.query:get_value()))
+					vim.ui.open(("%s/search/?q=%s"):format(config.url(), signal_issues.query:get_value()))
 				end,
 			}),
 			n.gap(1),
@@ -213,7 +219,7 @@ function M.get_issues(opts)
 			return
 		end
 
-		log.error(err)
+		log.p.error(err)
 	end)
 
 	signal_issues.query:debounce(c.debounce):observe(function(query)
@@ -226,7 +232,7 @@ function M.get_issues(opts)
 			component:set_border_text("bottom", "running...", "right")
 		end
 
-		lib.get_issues({ query = query }, function(err, res)
+		request = lib.get_issues({ query = query }, function(err, res, meta)
 			if err then
 				signal_issues.issues = {}
 				signal.error = err
@@ -255,7 +261,7 @@ function M.get_issues(opts)
 			end, res or {})
 
 			if component ~= nil then
-				component:set_border_text("bottom", ("matches: %d"):format(#(res or {})), "right")
+				component:set_border_text("bottom", utils.format_offline(("matches: %d"):format(#(res or {})), meta), "right")
 			end
 		end)
 
@@ -510,13 +516,22 @@ function M.get_issue(opts)
 								id = signal_issue.issue:get_value().id,
 								summary = s,
 								description = d,
-							}, function(err, _)
+							}, function(err, _, meta)
 								if err then
 									log.p.error(err)
 
 									return
 								end
 
+								if meta and meta.queued then
+									log.warn(
+										"Youtrack is not reachable, update is queued: %s",
+										signal_issue.issue:get_value().text
+									)
+
+									return
+								end
+
 								log.info("Issue updated: %s", signal_issue.issue:get_value().text)
 
 								signal_issue.should_refresh = true
@@ -532,13 +547,23 @@ function M.get_issue(opts)
 					if command and command:get_current_value() ~= nil and command:get_current_value() ~= "" then
 						lib.apply_issue_command(
 							{ id = signal_issue.issue:get_value().id, query = command:get_current_value() },
-							function(err, _)
+							function(err, _, meta)
 								if err then
 									log.p.error(err)
 
 									return
 								end
 
+								if meta and meta.queued then
+									log.warn(
+										"Youtrack is not reachable, command is queued: %s -> %s",
+										signal_issue.issue:get_value().text,
+										command:get_current_value()
+									)
+
+									return
+								end
+
 								log.info(
 									"Command applied to issue: %s -> %s",
 									signal_issue.issue:get_value().text,
@@ -559,13 +584,24 @@ function M.get_issue(opts)
 						lib.add_issue_comment({
 							id = signal_issue.issue:get_value().id,
 							comment = vim.fn.join(utils.get_component_buffer_content(comment), "\n"),
-						}, function(err, _)
+						}, function(err, _, meta)
 							if err then
 								log.p.error(err)
 
 								return
 							end
 
+							if meta and meta.queued then
+								log.warn(
+									"Youtrack is not reachable, comment is queued: %s",
+									signal_issue.issue:get_value().text
+								)
+
+								utils.set_component_buffer_content(comment, nil)
+
+								return
+							end
+
 							log.info("Comment applied to issue: %s", signal_issue.issue:get_value().text)
 
 							utils.set_component_buffer_content(comment, nil)
@@ -594,7 +630,7 @@ function M.get_issue(opts)
 				autofocus = false,
 				border_style = c.ui.border,
 				on_press = function()
-					vim.ui.open(("%s/issue/%s"):format(c.url, signal_issue.issue:get_value().text))
+					vim.ui.open(("%s/issue/%s"):format(config.url(), signal_issue.issue:get_value().text))
 				end,
 			}),
 			n.gap(1),
@@ -618,7 +654,7 @@ function M.get_issue(opts)
 				return
 			end
 
-			log.error(err)
+			log.p.error(err)
 		end)
 
 		signal_issue.should_refresh:observe(function(should_refresh)
@@ -630,7 +666,7 @@ function M.get_issue(opts)
 
 				log.debug("Should refresh the given issue: %s", opts.id)
 
-				lib.get_issue({ id = opts.id }, function(err, res)
+				lib.get_issue({ id = opts.id }, function(err, res, meta)
 					if err then
 						signal.error = err
 						if issue_header ~= nil then
@@ -724,7 +760,7 @@ function M.get_issue(opts)
 					end
 
 					if issue_header ~= nil then
-						issue_header:set_border_text("bottom", nil, "right")
+						issue_header:set_border_text("bottom", meta and utils.format_offline(nil, meta), "right")
 					end
 					signal.active = "issue"
 				end)
@@ -891,7 +927,7 @@ function M.create_issue(opts)
 		)
 	)
 
-	lib.get_projects(nil, function(err, res)
+	lib.get_projects(nil, function(err, res, meta)
 		if err then
 			log.p.error(err)
 
@@ -899,7 +935,7 @@ function M.create_issue(opts)
 		end
 
 		vim.ui.select(res, {
-			prompt = "Select project",
+			prompt = utils.format_offline("Select project", meta),
 			format_item = function(item)
 				return item.text
 			end,
@@ -87,7 +87,7 @@ function M.setup(config)
 				local passed = { ... }
 				local fmt = table.remove(passed, 1)
 
-				return fmt
+				return tostring(fmt)
 			end, ...)
 		end
 	end
@@ -0,0 +1,162 @@
+local M = {}
+
+local lib = require("youtrack.lib")
+local log = require("youtrack.log")
+local config = require("youtrack.config")
+
+---@class youtrack.OutboxEntry
+---@field id number
+---@field profile string
+---@field operation table Queued arguments of the library function, with its name in `type`.
+---@field queued_at string
+---@field attempts number
+---@field error? string Error of the last rejected replay, such changes are only replayed when retried by their id.
+
+---@class youtrack.OutboxMeta
+---@field offline boolean
+---@field queued boolean Mutation has been queued, since the server is not reachable.
+---@field id number
+
+---@class youtrack.RetryOutboxResult
+---@field replayed number
+---@field failed number Changes that have been rejected, which are kept until they are retried by their id or discarded.
+---@field pending number
+---@field offline boolean Replay stopped, since the server is still not reachable.
+---@field busy boolean Nothing has been replayed, since another replay is in progress.
+
+---@type uv_timer_t?
+local timer = nil
+
+---Missing errors are serialized as `vim.NIL`, which is truthy.
+---@param entry youtrack.OutboxEntry
+---@return boolean
+local function is_rejected(entry)
+	return entry.error ~= nil and entry.error ~= vim.NIL
+end
+
+---@param entry youtrack.OutboxEntry
+---@return string
+local function format_entry(entry)
+	local operation = entry.operation
+
+	return ("#%d %s %s [%s]%s"):format(
+		entry.id,
+		operation.type,
+		operation.id,
+		entry.queued_at,
+		is_rejected(entry) and (" (%s)"):format(entry.error) or ""
+	)
+end
+
+---@class youtrack.GetOutboxOptions
+
+---@param opts? youtrack.GetOutboxOptions
+function M.get_outbox(opts)
+	opts = opts or {}
+
+	---@type youtrack.OutboxEntry[]
+	local entries = lib.get_outbox()
+
+	if #entries == 0 then
+		log.info("There are no queued changes.")
+
+		return
+	end
+
+	vim.ui.select(entries, {
+		prompt = "Select queued change",
+		format_item = format_entry,
+	}, function(entry)
+		if not entry then
+			return
+		end
+
+		vim.ui.select({ "retry", "discard" }, {
+			prompt = format_entry(entry),
+		}, function(action)
+			if action == "retry" then
+				M.retry_outbox({ id = entry.id })
+			elseif action == "discard" then
+				M.discard_outbox({ id = entry.id })
+			end
+		end)
+	end)
+end
+
+---@class youtrack.RetryOutboxOptions
+---@field id? number Retries only the given change, all of them in order except the rejected ones when not set.
+---@field silent? boolean Does not log when there is nothing to replay or the server is still not reachable.
+
+---@param opts? youtrack.RetryOutboxOptions
+function M.retry_outbox(opts)
+	opts = opts or {}
+
+	return lib.retry_outbox({ id = opts.id }, function(err, res)
+		if err then
+			log.p.error(err)
+
+			return
+		end
+
+		---@cast res youtrack.RetryOutboxResult
+
+		if res.busy then
+			if not opts.silent then
+				log.info("Queued changes are being replayed already, %d are pending.", res.pending)
+			end
+		elseif res.offline and not opts.silent then
+			log.warn("Youtrack is still not reachable, %d changes are queued.", res.pending)
+		elseif res.replayed > 0 or not opts.silent then
+			log.info("Replayed %d queued changes, %d are pending.", res.replayed, res.pending)
+		end
+
+		if res.failed > 0 then
+			log.warn("%d queued changes have been rejected, they are kept until retried or discarded.", res.failed)
+		end
+	end)
+end
+
+---@class youtrack.DiscardOutboxOptions
+---@field id? number Discards only the given change, all of them when not set.
+
+---@param opts? youtrack.DiscardOutboxOptions
+function M.discard_outbox(opts)
+	opts = opts or {}
+
+	local ok, res = pcall(lib.discard_outbox, { id = opts.id })
+
+	if not ok then
+		log.p.error(res)
+
+		return
+	end
+
+	log.info("Discarded %d queued changes.", res)
+end
+
+--- Replays the queued changes periodically, while there are any.
+function M.start()
+	local c = config.read().offline
+
+	if timer or not c.enabled or not c.outbox or (c.interval or 0) <= 0 then
+		return
+	end
+
+	timer = vim.uv.new_timer()
+
+	timer:start(
+		0,
+		c.interval,
+		vim.schedule_wrap(function()
+			local pending = vim.tbl_filter(function(entry)
+				return not is_rejected(entry)
+			end, lib.get_outbox())
+
+			if #pending > 0 then
+				M.retry_outbox({ silent = true })
+			end
+		end)
+	)
+end
+
+return M
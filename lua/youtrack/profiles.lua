@@ -0,0 +1,50 @@
+local M = {}
+
+local lib = require("youtrack.lib")
+local log = require("youtrack.log")
+local config = require("youtrack.config")
+
+---@class youtrack.Profile
+---@field name string
+---@field url string
+---@field active boolean
+
+---@class youtrack.GetProfilesOptions
+
+---@param opts? youtrack.GetProfilesOptions
+function M.get_profiles(opts)
+	opts = opts or {}
+
+	---@type youtrack.Profile[]
+	local profiles = lib.get_profiles()
+
+	vim.ui.select(profiles, {
+		prompt = "Select profile",
+		format_item = function(item)
+			return ("%s%s [%s]"):format(item.active and "* " or "", item.name, item.url)
+		end,
+	}, function(profile)
+		if not profile then
+			return
+		end
+
+		M.set_profile(profile.name)
+	end)
+end
+
+---@param name string
+function M.set_profile(name)
+	local ok, res = pcall(lib.set_profile, { name = name })
+
+	if not ok then
+		log.p.error(res)
+
+		return
+	end
+
+	config.read().profile = res.name
+
+	log.info("Switched to profile: %s", res.name)
+end
+
+return M
@@ -4,12 +4,13 @@ local lib = require("youtrack.lib")
 local log = require("youtrack.log")
 local n = require("nui-components")
 local config = require("youtrack.config")
+local utils = require("youtrack.utils")
 
 ---@return nil
 function M.get_queries()
 	local c = config.read()
 
-	lib.get_saved_queries(nil, function(e, r)
+	lib.get_saved_queries(nil, function(e, r, meta)
 		local queries = { { name = "Create a new query...", query = "" } }
 
 		vim.list_extend(queries, c.queries)
@@ -26,7 +27,7 @@ function M.get_queries()
 		end
 
 		vim.ui.select(queries, {
-			prompt = "Select query",
+			prompt = utils.format_offline("Select query", meta),
 			format_item = function(item)
 				return ("%s [%s]"):format(item.name, item.query)
 			end,
@@ -0,0 +1,240 @@
+---@meta
+-- Generated by build.rs from src/client.rs and src/config.rs, do not edit.
+
+---@class youtrack.lib.Pagination
+---@field skip? number
+---@field take? number
+
+---@class youtrack.lib.SavedQuery
+---@field id string
+---@field name string
+---@field query string
+
+---@class youtrack.lib.Issue
+---@field id string
+---@field text string
+---@field summary string
+---@field description? string
+---@field project youtrack.lib.Project
+---@field fields youtrack.lib.Field[]
+---@field tags youtrack.lib.Tag[]
+---@field comments? youtrack.lib.Comment[]
+
+---@class youtrack.lib.Project
+---@field id string
+---@field name string
+---@field text string
+
+---@class youtrack.lib.Agile
+---@field id string
+---@field name string
+---@field projects youtrack.lib.Project[]
+---@field sprints youtrack.lib.Sprint[]
+
+---@class youtrack.lib.Sprint
+---@field id string
+---@field name string
+---@field is_default boolean
+
+---@class youtrack.lib.Tag
+---@field id string
+---@field name string
+---@field color any
+
+---@class youtrack.lib.Comment
+---@field author string
+---@field text string
+---@field created_at string
+
+---@class youtrack.lib.Field
+---@field id string
+---@field name string
+---@field text string
+---@field value? any
+---@field values? any[]
+
+---@class youtrack.lib.ApiEndpoint
+---@field url string
+---@field hub_url string
+---@field version? string
+---@field build? string
+
+---@class youtrack.lib.ValidateApi
+---@field profile? string
+
+---@class youtrack.lib.User
+---@field id string
+---@field login string
+---@field name string
+---@field email? string
+
+--- Optional features of the server, which are unknown when the user is not permitted to see them.
+---@class youtrack.lib.ServerFeatures
+---@field time_tracking? boolean
+---@field knowledge_base? boolean
+---@field helpdesk? boolean
+
+---@class youtrack.lib.Connection
+---@field profile string
+---@field url string
+---@field user youtrack.lib.User
+---@field version? string
+---@field build? string
+---@field features youtrack.lib.ServerFeatures
+
+---@class youtrack.lib.CheckConnection
+---@field profile? string
+
+---@class youtrack.lib.GetSavedQueries
+---@field page? youtrack.lib.Pagination
+---@field profile? string
+
+---@class youtrack.lib.GetIssues
+---@field query? string
+---@field page? youtrack.lib.Pagination
+---@field profile? string
+
+---@class youtrack.lib.GetIssue
+---@field id string
+---@field profile? string
+
+---@class youtrack.lib.CreateIssue
+---@field project string
+---@field summary string
+---@field description? string
+---@field profile? string
+
+---@class youtrack.lib.UpdateIssue
+---@field id string
+---@field description? string
+---@field summary? string
+---@field profile? string
+
+---@class youtrack.lib.ApplyIssueCommand
+---@field id string
+---@field query string
+---@field profile? string
+
+---@class youtrack.lib.AddIssueComment
+---@field id string
+---@field comment string
+---@field profile? string
+
+---@class youtrack.lib.GetProjects
+---@field profile? string
+
+---@class youtrack.lib.GetAgiles
+---@field profile? string
+
+---@class youtrack.lib.Request
+---@field method? string HTTP method, defaults to GET.
+---@field path string Path relative to the REST API of the profile, like `issues/PRJ-1/comments`, which can not leave it even with encoded segments.
+---@field query? table<string, any>
+---@field fields? string Fields to return, which Youtrack only returns the identifiers of by default.
+---@field body? any
+---@field profile? string
+
+---@class youtrack.lib.Config
+---@field url string
+---@field token youtrack.lib.ConfigToken
+---@field hub_url? string Url of the Hub service when it is not served together with Youtrack.
+---@field issues youtrack.lib.ConfigIssuesIssues
+---@field issue youtrack.lib.ConfigIssuesIssue
+---@field http? youtrack.lib.ConfigHttp
+---@field retry? youtrack.lib.ConfigRetry
+---@field cache? youtrack.lib.ConfigCache
+---@field offline? youtrack.lib.ConfigOffline
+---@field cancel_previous? string[] Exported functions that cancel their previous in-flight call when called again.
+---@field log? youtrack.lib.ConfigLog
+---@field recorder? youtrack.lib.ConfigRecorder
+---@field backend? youtrack.lib.ConfigBackend
+---@field profile? string Name of the profile that is active after the setup.
+---@field profiles? table<string, youtrack.lib.ConfigProfile> Additional Youtrack instances, where the top level configuration is the default profile.
+
+--- Source of the token, which can be given directly or read from somewhere else.
+---@alias youtrack.lib.ConfigToken
+---| string
+---| { env: string } # Name of the environment variable that contains the token.
+---| { file: string } # Path of the file that contains the token.
+---| { command: string } # Shell command that prints the token to stdout.
+
+---@class youtrack.lib.ConfigProfile
+---@field url string
+---@field token youtrack.lib.ConfigToken
+---@field hub_url? string
+---@field issues? youtrack.lib.ConfigIssuesIssues Falls back to the top level configuration when not set.
+---@field issue? youtrack.lib.ConfigIssuesIssue Falls back to the top level configuration when not set.
+
+---@class youtrack.lib.ConfigIssuesIssues
+---@field fields string[]
+
+---@class youtrack.lib.ConfigIssuesIssue
+---@field fields string[]
+
+---@class youtrack.lib.ConfigHttp
+---@field connect_timeout? number Timeout in milliseconds for establishing the connection.
+---@field timeout? number Timeout in milliseconds for the whole request.
+---@field proxy? string Proxy url that will be used for both HTTP and HTTPS requests.
+---@field no_proxy? string[] Hosts that should bypass the proxy.
+---@field ca_certificates? string[] PEM files with additional root certificates to trust.
+---@field identity? youtrack.lib.ConfigHttpIdentity
+---@field insecure? boolean Disables the certificate verification, should only be used for local testing.
+---@field max_concurrent? number Maximum amount of requests in flight at once, others wait in the order they were made.
+
+--- Client certificate for mutual TLS.
+---@class youtrack.lib.ConfigHttpIdentity
+---@field cert string PEM file of the client certificate.
+---@field key string PEM file of the PKCS#8 private key.
+
+---@class youtrack.lib.ConfigRetry
+---@field enabled? boolean
+---@field attempts? number Maximum amount of retries after the first attempt.
+---@field delay? number Initial delay in milliseconds before the first retry.
+---@field max_delay? number Upper bound in milliseconds for a single delay.
+---@field factor? number
+
+---@class youtrack.lib.ConfigCache
+---@field enabled? boolean
+---@field ttl? table<string, number> Time in seconds per exported function that the response is fresh, others are not cached.
+---@field stale? number Time in seconds after the response gets stale, where it is still served while revalidating.
+
+---@class youtrack.lib.ConfigOffline
+---@field enabled? boolean
+---@field path? string Directory where the responses are persisted, nothing is persisted when not set.
+---@field endpoints? string[] Exported functions whose responses are persisted.
+---@field max_entries? number Responses that are kept per function, where the least recently stored ones are removed.
+---@field outbox? boolean Queues the mutations that can not be sent while offline to replay them later.
+
+---@class youtrack.lib.ConfigLog
+---@field level? string Level of the forwarded records, like `info` or `debug`.
+---@field targets? table<string, string> Levels per target prefix, like `youtrack_nvim::request` or `reqwest`, that override the level.
+---@field file? youtrack.lib.ConfigLogFile Writes the records as JSON lines to a file as well, when set.
+---@field redact? youtrack.lib.ConfigRedact
+
+---@class youtrack.lib.ConfigLogFile
+---@field path string Has no default, since the plugin defaults it to `youtrack/youtrack.jsonl` in the state directory of Neovim.
+---@field max_size? number Size in bytes after the file is rotated.
+---@field max_files? number Amount of rotated files that are kept besides the current one.
+
+--- Masks the authorization header and tokens in the log records, and optionally personal data.
+---@class youtrack.lib.ConfigRedact
+---@field enabled? boolean
+---@field emails? boolean
+---@field issue_text? boolean Summaries, descriptions and comments of issues.
+
+---@alias youtrack.lib.RecorderMode
+---| "off"
+---| "record" # Writes every exchange with the server to the recording.
+---| "replay" # Serves the responses from the recording instead of sending the requests.
+
+---@class youtrack.lib.ConfigRecorder
+---@field mode? youtrack.lib.RecorderMode
+---@field path? string HAR file of the recording.
+
+---@alias youtrack.lib.BackendKind
+---| "http"
+---| "memory" # Serves the operations from memory, seeded from the fixture.
+
+---@class youtrack.lib.ConfigBackend
+---@field kind? youtrack.lib.BackendKind
+---@field fixture? string JSON file with the projects, issues, saved queries and agiles of the memory backend.
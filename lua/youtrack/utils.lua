@@ -146,4 +146,22 @@ function M.calculate_ui(ui)
 	return result
 end
 
+---Marks the text when the response has been served from the offline storage.
+---@param text string?
+---@param meta youtrack.ResponseMeta?
+---@return string?
+function M.format_offline(text, meta)
+	if not meta or not meta.offline then
+		return text
+	end
+
+	local marker = ("offline: %s"):format(meta.fetched_at)
+
+	if text == nil then
+		return marker
+	end
+
+	return ("%s (%s)"):format(text, marker)
+end
+
 return M
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use serde_json::Value as JsonValue;
+
+use crate::client::{
+    AddIssueComment, Agile, ApiEndpoint, ApplyIssueCommand, Client, Connection, CreateIssue,
+    GetAgiles, GetIssue, GetIssues, GetProjects, GetSavedQueries, HttpBackend, Issue, Project,
+    SavedQuery, UpdateIssue,
+};
+use crate::config::{BackendKind, ConfigBackend};
+use crate::error::Error;
+use crate::memory::MemoryBackend;
+use crate::profile::Profile;
+
+/// Operations on a Youtrack instance, where a response that the server rejects is returned as
+/// [`Error::Api`] and everything else is a failure of the transport.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    async fn validate_api(&self, c: &Client, p: &Profile) -> Result<ApiEndpoint, Error>;
+
+    async fn check_connection(&self, c: &Client, p: &Profile) -> Result<Connection, Error>;
+
+    async fn get_saved_queries(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetSavedQueries,
+    ) -> Result<Vec<SavedQuery>, Error>;
+
+    async fn get_issues(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetIssues,
+    ) -> Result<Vec<Issue>, Error>;
+
+    async fn get_issue(&self, c: &Client, p: &Profile, options: &GetIssue) -> Result<Issue, Error>;
+
+    /// Internal and readable id of the issue, which can be looked up by either of them.
+    async fn get_issue_ids(
+        &self,
+        c: &Client,
+        p: &Profile,
+        id: &str,
+    ) -> Result<(String, String), Error> {
+        let options = GetIssue {
+            id: id.to_string(),
+            profile: Some(p.name.clone()),
+        };
+        let issue = self.get_issue(c, p, &options).await?;
+
+        Ok((issue.id, issue.text))
+    }
+
+    async fn create_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &CreateIssue,
+    ) -> Result<Issue, Error>;
+
+    async fn update_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &UpdateIssue,
+    ) -> Result<JsonValue, Error>;
+
+    async fn apply_issue_command(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &ApplyIssueCommand,
+    ) -> Result<JsonValue, Error>;
+
+    async fn add_issue_comment(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &AddIssueComment,
+    ) -> Result<JsonValue, Error>;
+
+    async fn get_projects(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetProjects,
+    ) -> Result<Vec<Project>, Error>;
+
+    async fn get_agiles(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetAgiles,
+    ) -> Result<Vec<Agile>, Error>;
+}
+
+/// Creates the backend that is selected in the configuration.
+pub fn new(config: &ConfigBackend) -> Result<Box<dyn Backend + Send + Sync>, Error> {
+    Ok(match config.kind {
+        BackendKind::Http => Box::new(HttpBackend),
+        BackendKind::Memory => Box::new(MemoryBackend::load(config.fixture.as_deref())?),
+    })
+}
@@ -0,0 +1,494 @@
+use mlua::prelude::*;
+use mlua::{AppDataRef, Lua};
+
+use crate::cache::{CacheKey, Cached};
+use crate::client::*;
+use crate::error::Error;
+use crate::events::{self, Event, IssueIds};
+use crate::lua::NoData;
+use crate::outbox::OutboxOperation;
+use crate::Module;
+
+pub type ValidateApiArgs<'lua> = (Option<ValidateApi>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn validate_api(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): ValidateApiArgs<'_>,
+) -> Result<NoData, Error> {
+    match m
+        .client
+        .validate_api(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(result) => {
+            log::debug!("Youtrack api validated: {:#?}", result);
+            callback.call::<_, ()>((LuaNil, lua.to_value(&result)))?;
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!("Youtrack api can not be validated: {:?}", err);
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type CheckConnectionArgs<'lua> = (Option<CheckConnection>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn check_connection(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): CheckConnectionArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.as_ref().and_then(|o| o.profile.as_deref()))?;
+
+    match m
+        .client
+        .check_connection(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(result) => {
+            log::debug!(
+                "Youtrack connection checked: {:?} -> {:#?}",
+                options,
+                result
+            );
+            callback.call::<_, ()>((LuaNil, lua.to_value(&result)))?;
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack connection check failed: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => {
+            log::debug!("Youtrack is not reachable: {:?} -> {}", options, err);
+            callback.call::<_, ()>((
+                format!("Youtrack is not reachable at {}: {}", p.api_url, err),
+                LuaNil,
+            ))?;
+        }
+    }
+
+    Ok(NoData)
+}
+
+pub type GetSavedQueriesArgs<'lua> = (Option<GetSavedQueries>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn get_saved_queries(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): GetSavedQueriesArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.as_ref().and_then(|o| o.profile.as_deref()))?;
+    let key = CacheKey::new(p, "get_saved_queries", &options);
+
+    let cached = m.cached(&key);
+    if let Some(ref cached) = cached {
+        log::debug!("Youtrack saved queries served from cache: {:?}", options);
+        callback.call::<_, ()>((LuaNil, lua.to_value(cached.value())?))?;
+    }
+
+    let revalidate = match cached {
+        Some(Cached::Fresh(_)) => return Ok(NoData),
+        Some(Cached::Stale(_)) => true,
+        None => false,
+    };
+
+    match m
+        .client
+        .get_saved_queries(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(result) => {
+            log::debug!(
+                "Youtrack saved queries fetched: {:?} -> {:#?}",
+                options,
+                result
+            );
+            m.store_offline(&key, &result).await;
+            m.cache(key, &result)?;
+            if !revalidate {
+                callback.call::<_, ()>((LuaNil, lua.to_value(&result)))?;
+            }
+        }
+        Err(err) if err.is_offline() && revalidate => {}
+        Err(err) if err.is_offline() => return m.serve_offline(lua, &key, callback, err).await,
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack saved queries can not be fetched: {:?} -> {:#?}",
+                options,
+                err
+            );
+            if !revalidate {
+                callback.call::<_, ()>((err, LuaNil))?;
+            }
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type GetIssuesArgs<'lua> = (Option<GetIssues>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn get_issues(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): GetIssuesArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.as_ref().and_then(|o| o.profile.as_deref()))?;
+    let key = CacheKey::new(p, "get_issues", &options);
+
+    match m
+        .client
+        .get_issues(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(processed) => {
+            log::debug!(
+                "Youtrack issues matching: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                processed
+            );
+            m.store_offline(&key, &processed).await;
+            callback.call::<_, ()>((LuaNil, lua.to_value(&processed)))?;
+        }
+        Err(err) if err.is_offline() => return m.serve_offline(lua, &key, callback, err).await,
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issues can not be fetched: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type GetIssueArgs<'lua> = (GetIssue, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn get_issue(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): GetIssueArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.profile.as_deref())?;
+    let key = CacheKey::new(p, "get_issue", &options);
+
+    match m.client.get_issue(&options).await {
+        Ok(processed) => {
+            log::debug!("Youtrack issue details: {:?} -> {:#?}", options, processed);
+            m.store_offline(&key, &processed).await;
+            callback.call::<_, ()>((LuaNil, lua.to_value(&processed)))?;
+        }
+        Err(err) if err.is_offline() => return m.serve_offline(lua, &key, callback, err).await,
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issue details can not be fetched: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type CreateIssueArgs<'lua> = (CreateIssue, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn create_issue(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): CreateIssueArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.profile.as_deref())?;
+
+    match m.client.create_issue(&options).await {
+        Ok(processed) => {
+            log::debug!("Youtrack issue created: {:?} -> {:#?}", options, processed);
+            let ids = IssueIds::new(&processed);
+            events::emit(lua, Event::IssueCreated, p, &ids, &options, &processed);
+            callback.call::<_, ()>((LuaNil, lua.to_value(&processed)))?;
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issue can not be created: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type UpdateIssueArgs<'lua> = (UpdateIssue, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn update_issue(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): UpdateIssueArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.profile.as_deref())?;
+
+    match m.client.update_issue(&options).await {
+        Ok(json) => {
+            log::debug!("Youtrack issue updated: {:?} -> {:#?}", options, json);
+            let called = callback.call::<_, ()>((LuaNil, lua.to_value(&json)));
+            let ids = IssueIds::resolve(&m.client, p, &options.id, &json).await;
+            events::emit(lua, Event::IssueUpdated, p, &ids, &options, &json);
+            called?;
+        }
+        Err(err) if err.is_unreachable() => {
+            return m.queue(p, OutboxOperation::UpdateIssue(options), callback, err)
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issue can not be updated: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type ApplyIssueCommandArgs<'lua> = (ApplyIssueCommand, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn apply_issue_command(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): ApplyIssueCommandArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.profile.as_deref())?;
+
+    match m.client.apply_issue_command(&options).await {
+        Ok(json) => {
+            log::debug!(
+                "Youtrack issue command applied: {:?} -> {:#?}",
+                options,
+                json
+            );
+            let called = callback.call::<_, ()>((LuaNil, lua.to_value(&json)));
+            let ids = IssueIds::resolve(&m.client, p, &options.id, &json).await;
+            events::emit(lua, Event::CommandApplied, p, &ids, &options, &json);
+            called?;
+        }
+        Err(err) if err.is_unreachable() => {
+            return m.queue(
+                p,
+                OutboxOperation::ApplyIssueCommand(options),
+                callback,
+                err,
+            )
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issue command can not be applied: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type AddIssueCommentArgs<'lua> = (AddIssueComment, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn add_issue_comment(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): AddIssueCommentArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.profile.as_deref())?;
+
+    match m.client.add_issue_comment(&options).await {
+        Ok(json) => {
+            log::debug!("Youtrack issue comment added: {:?} -> {:#?}", options, json);
+            let called = callback.call::<_, ()>((LuaNil, lua.to_value(&json)));
+            let ids = IssueIds::resolve(&m.client, p, &options.id, &json).await;
+            events::emit(lua, Event::CommentAdded, p, &ids, &options, &json);
+            called?;
+        }
+        Err(err) if err.is_unreachable() => {
+            return m.queue(p, OutboxOperation::AddIssueComment(options), callback, err)
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack issue comment can not be added: {:?} -> {:#?}",
+                options,
+                err
+            );
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type GetProjectsArgs<'lua> = (Option<GetProjects>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn get_projects(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): GetProjectsArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.as_ref().and_then(|o| o.profile.as_deref()))?;
+    let key = CacheKey::new(p, "get_projects", &options);
+
+    let cached = m.cached(&key);
+    if let Some(ref cached) = cached {
+        log::debug!("Youtrack projects served from cache: {:?}", options);
+        callback.call::<_, ()>((LuaNil, lua.to_value(cached.value())?))?;
+    }
+
+    let revalidate = match cached {
+        Some(Cached::Fresh(_)) => return Ok(NoData),
+        Some(Cached::Stale(_)) => true,
+        None => false,
+    };
+
+    match m
+        .client
+        .get_projects(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(processed) => {
+            log::debug!(
+                "Youtrack projects matching: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                processed
+            );
+            m.store_offline(&key, &processed).await;
+            m.cache(key, &processed)?;
+            if !revalidate {
+                callback.call::<_, ()>((LuaNil, lua.to_value(&processed)))?;
+            }
+        }
+        Err(err) if err.is_offline() && revalidate => {}
+        Err(err) if err.is_offline() => return m.serve_offline(lua, &key, callback, err).await,
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack projects can not be fetched: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                err
+            );
+            if !revalidate {
+                callback.call::<_, ()>((err, LuaNil))?;
+            }
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type GetAgilesArgs<'lua> = (Option<GetAgiles>, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn get_agiles(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): GetAgilesArgs<'_>,
+) -> Result<NoData, Error> {
+    let p = m.profile(options.as_ref().and_then(|o| o.profile.as_deref()))?;
+    let key = CacheKey::new(p, "get_agiles", &options);
+
+    let cached = m.cached(&key);
+    if let Some(ref cached) = cached {
+        log::debug!("Youtrack agiles served from cache: {:?}", options);
+        callback.call::<_, ()>((LuaNil, lua.to_value(cached.value())?))?;
+    }
+
+    let revalidate = match cached {
+        Some(Cached::Fresh(_)) => return Ok(NoData),
+        Some(Cached::Stale(_)) => true,
+        None => false,
+    };
+
+    match m
+        .client
+        .get_agiles(&options.clone().unwrap_or_default())
+        .await
+    {
+        Ok(processed) => {
+            log::debug!(
+                "Youtrack agiles matching: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                processed
+            );
+            m.store_offline(&key, &processed).await;
+            m.cache(key, &processed)?;
+            if !revalidate {
+                callback.call::<_, ()>((LuaNil, lua.to_value(&processed)))?;
+            }
+        }
+        Err(err) if err.is_offline() && revalidate => {}
+        Err(err) if err.is_offline() => return m.serve_offline(lua, &key, callback, err).await,
+        Err(err @ Error::Api(_)) => {
+            log::debug!(
+                "Youtrack agiles can not be fetched: {:?} -> {:#?}",
+                options.unwrap_or_default(),
+                err
+            );
+            if !revalidate {
+                callback.call::<_, ()>((err, LuaNil))?;
+            }
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
+
+pub type RequestArgs<'lua> = (Request, LuaFunction<'lua>);
+
+#[allow(unused_variables)]
+pub async fn request(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): RequestArgs<'_>,
+) -> Result<NoData, Error> {
+    match m.client.request(&options).await {
+        Ok(json) => {
+            log::debug!("Youtrack response: {:?} -> {:#?}", options, json);
+            callback.call::<_, ()>((LuaNil, lua.to_value(&json)))?;
+        }
+        Err(err @ Error::Api(_)) => {
+            log::debug!("Youtrack request failed: {:?} -> {:#?}", options, err);
+            callback.call::<_, ()>((err, LuaNil))?;
+        }
+        Err(err) => return Err(err),
+    }
+
+    Ok(NoData)
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use mlua::{AppDataRef, Lua};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::config::ConfigCache;
+use crate::error::Error;
+use crate::macros::{from_lua, into_lua};
+use crate::profile::Profile;
+use crate::Module;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub profile: String,
+    pub endpoint: &'static str,
+    pub options: String,
+}
+
+impl CacheKey {
+    pub fn new<T: Serialize>(profile: &Profile, endpoint: &'static str, options: &T) -> Self {
+        Self {
+            profile: profile.name.clone(),
+            endpoint,
+            options: serde_json::to_string(options).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    value: JsonValue,
+    fetched_at: Instant,
+}
+
+/// In-memory cache for the responses of the endpoints that rarely change.
+#[derive(Debug, Default)]
+pub struct ResponseCache(Mutex<HashMap<CacheKey, CacheEntry>>);
+
+/// Response from the cache, where a stale one should be served while it gets revalidated.
+#[derive(Debug)]
+pub enum Cached {
+    Fresh(JsonValue),
+    Stale(JsonValue),
+}
+
+impl Cached {
+    pub fn value(&self) -> &JsonValue {
+        match self {
+            Cached::Fresh(value) | Cached::Stale(value) => value,
+        }
+    }
+}
+
+impl ResponseCache {
+    fn ttl(config: &ConfigCache, endpoint: &str) -> Option<Duration> {
+        if !config.enabled {
+            return None;
+        }
+
+        config
+            .ttl
+            .get(endpoint)
+            .map(|ttl| Duration::from_secs(*ttl))
+    }
+
+    pub fn get(&self, config: &ConfigCache, key: &CacheKey) -> Option<Cached> {
+        let ttl = Self::ttl(config, key.endpoint)?;
+        let stale = Duration::from_secs(config.stale);
+
+        let cache = self.0.lock().unwrap();
+        let entry = cache.get(key)?;
+        let age = entry.fetched_at.elapsed();
+
+        if age < ttl {
+            Some(Cached::Fresh(entry.value.clone()))
+        } else if age < ttl + stale {
+            Some(Cached::Stale(entry.value.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn insert<T: Serialize>(
+        &self,
+        config: &ConfigCache,
+        key: CacheKey,
+        value: &T,
+    ) -> Result<(), Error> {
+        if Self::ttl(config, key.endpoint).is_none() {
+            return Ok(());
+        }
+
+        let value = serde_json::to_value(value).map_err(|err| Error::Std(Box::new(err)))?;
+
+        self.0.lock().unwrap().insert(
+            key,
+            CacheEntry {
+                value,
+                fetched_at: Instant::now(),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Removes the matching responses and returns how many of them there were.
+    pub fn invalidate(&self, options: &InvalidateCache) -> usize {
+        let mut cache = self.0.lock().unwrap();
+        let count = cache.len();
+
+        cache.retain(|key, _| {
+            options
+                .endpoint
+                .as_ref()
+                .is_some_and(|endpoint| endpoint != key.endpoint)
+                || options
+                    .profile
+                    .as_ref()
+                    .is_some_and(|profile| *profile != key.profile)
+        });
+
+        count - cache.len()
+    }
+}
+
+impl Module {
+    pub fn cached(&self, key: &CacheKey) -> Option<Cached> {
+        self.cache.get(&self.client.config.cache, key)
+    }
+
+    pub fn cache<T: Serialize>(&self, key: CacheKey, value: &T) -> Result<(), Error> {
+        self.cache.insert(&self.client.config.cache, key, value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InvalidateCache {
+    /// Name of the exported function to invalidate, all of them when not set.
+    pub endpoint: Option<String>,
+    pub profile: Option<String>,
+}
+
+into_lua!(InvalidateCache);
+from_lua!(InvalidateCache);
+
+pub fn invalidate_cache(
+    _: &Lua,
+    m: AppDataRef<'static, Module>,
+    options: Option<InvalidateCache>,
+) -> Result<usize, Error> {
+    let options = options.unwrap_or_default();
+    let count = m.cache.invalidate(&options);
+
+    log::debug!("Youtrack cache invalidated: {:?} -> {}", options, count);
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn config(ttl: u64, stale: u64) -> ConfigCache {
+        ConfigCache {
+            ttl: [("get_projects".to_string(), ttl)].into(),
+            stale,
+            ..ConfigCache::default()
+        }
+    }
+
+    fn key(profile: &str, endpoint: &'static str) -> CacheKey {
+        CacheKey {
+            profile: profile.to_string(),
+            endpoint,
+            options: "null".to_string(),
+        }
+    }
+
+    /// Pretends that the response was fetched the given amount of seconds ago.
+    fn age(cache: &ResponseCache, key: &CacheKey, secs: u64) {
+        let mut entries = cache.0.lock().unwrap();
+        let entry = entries.get_mut(key).unwrap();
+        entry.fetched_at = Instant::now()
+            .checked_sub(Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn fresh_responses_are_served() {
+        let cache = ResponseCache::default();
+        let config = config(60, 600);
+        let key = key("default", "get_projects");
+
+        assert!(cache.get(&config, &key).is_none());
+
+        cache
+            .insert(&config, key.clone(), &json!([{"id": "0-1"}]))
+            .unwrap();
+
+        let cached = cache.get(&config, &key).unwrap();
+        assert!(matches!(cached, Cached::Fresh(_)));
+        assert_eq!(cached.value(), &json!([{"id": "0-1"}]));
+    }
+
+    #[test]
+    fn stale_responses_are_served_until_refreshed() {
+        let cache = ResponseCache::default();
+        let config = config(60, 600);
+        let key = key("default", "get_projects");
+
+        cache.insert(&config, key.clone(), &json!(["old"])).unwrap();
+        age(&cache, &key, 120);
+
+        let cached = cache.get(&config, &key).unwrap();
+        assert!(matches!(cached, Cached::Stale(_)));
+        assert_eq!(cached.value(), &json!(["old"]));
+
+        cache.insert(&config, key.clone(), &json!(["new"])).unwrap();
+
+        let cached = cache.get(&config, &key).unwrap();
+        assert!(matches!(cached, Cached::Fresh(_)));
+        assert_eq!(cached.value(), &json!(["new"]));
+    }
+
+    #[test]
+    fn expired_responses_are_not_served() {
+        let cache = ResponseCache::default();
+        let config = config(60, 600);
+        let key = key("default", "get_projects");
+
+        cache.insert(&config, key.clone(), &json!([])).unwrap();
+        age(&cache, &key, 700);
+
+        assert!(cache.get(&config, &key).is_none());
+    }
+
+    #[test]
+    fn endpoints_without_ttl_are_not_cached() {
+        let cache = ResponseCache::default();
+
+        cache
+            .insert(&config(60, 600), key("default", "get_issues"), &json!([]))
+            .unwrap();
+        assert!(cache.0.lock().unwrap().is_empty());
+
+        let disabled = ConfigCache {
+            enabled: false,
+            ..config(60, 600)
+        };
+
+        cache
+            .insert(&disabled, key("default", "get_projects"), &json!([]))
+            .unwrap();
+        assert!(cache.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn mutations_invalidate_the_matching_responses() {
+        let cache = ResponseCache::default();
+        let config = ConfigCache::default();
+
+        for key in [
+            key("default", "get_projects"),
+            key("default", "get_agiles"),
+            key("work", "get_projects"),
+        ] {
+            cache.insert(&config, key, &json!([])).unwrap();
+        }
+
+        let count = cache.invalidate(&InvalidateCache {
+            endpoint: Some("get_projects".to_string()),
+            profile: Some("default".to_string()),
+        });
+        assert_eq!(count, 1);
+        assert!(cache
+            .get(&config, &key("default", "get_projects"))
+            .is_none());
+        assert!(cache.get(&config, &key("work", "get_projects")).is_some());
+
+        let count = cache.invalidate(&InvalidateCache {
+            endpoint: Some("get_projects".to_string()),
+            profile: None,
+        });
+        assert_eq!(count, 1);
+
+        assert_eq!(cache.invalidate(&InvalidateCache::default()), 1);
+        assert!(cache.get(&config, &key("default", "get_agiles")).is_none());
+    }
+}
@@ -0,0 +1,407 @@
+use std::io::Write;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+use validator::Validate;
+
+use crate::client::{
+    AddIssueComment, ApplyIssueCommand, Client, CreateIssue, GetIssue, GetIssues, GetProjects,
+    Issue, Pagination,
+};
+use crate::config::Config;
+use crate::error::Error;
+use crate::RUNTIME;
+
+/// Command-line client for Youtrack, which shares the requests and decoding with the Neovim module.
+#[derive(Debug, Parser)]
+#[command(name = "youtrack", version)]
+struct Cli {
+    /// JSON file with the options of the library, like `url`, `token` and `profiles`, which are
+    /// validated the same way as in the setup of the plugin. Defaults to `YOUTRACK_CONFIG`, or a
+    /// configuration from `YOUTRACK_URL` and `YOUTRACK_TOKEN`.
+    #[arg(long, global = true, env = "YOUTRACK_CONFIG")]
+    config: Option<String>,
+
+    /// Profile to use instead of the active one of the configuration.
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[arg(long, global = true, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Action {
+    /// Lists the issues matching a query.
+    List {
+        #[arg(default_value = "for: me #Unresolved")]
+        query: String,
+
+        #[arg(long, default_value_t = 50)]
+        take: i32,
+
+        #[arg(long, default_value_t = 0)]
+        skip: i32,
+    },
+    /// Shows the details of an issue.
+    Show { id: String },
+    /// Creates an issue in the project with the given id or short name.
+    Create {
+        project: String,
+
+        summary: String,
+
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Adds a comment to an issue.
+    Comment { id: String, text: String },
+    /// Applies a command to an issue, like `State Fixed`.
+    Command { id: String, query: String },
+}
+
+/// Runs the command-line client with the arguments of the process.
+pub fn run() -> ExitCode {
+    let cli = Cli::parse();
+
+    match RUNTIME.block_on(execute(&cli, &mut std::io::stdout())) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_config(path: Option<&str>) -> Result<Config, Error> {
+    let config: Config = match path {
+        Some(path) => {
+            let content = std::fs::read(path).map_err(|err| Error::Std(Box::new(err)))?;
+
+            serde_json::from_slice(&content).map_err(|err| Error::Std(Box::new(err)))?
+        }
+        None => serde_json::from_value(json!({
+            "url": std::env::var("YOUTRACK_URL").map_err(|_| {
+                Error::Str("Either --config or YOUTRACK_URL should be given.".to_string())
+            })?,
+            "token": { "env": "YOUTRACK_TOKEN" },
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+        }))
+        .map_err(|err| Error::Std(Box::new(err)))?,
+    };
+
+    config.validate()?;
+
+    Ok(config)
+}
+
+async fn execute(cli: &Cli, out: &mut impl Write) -> Result<(), Error> {
+    let client = Client::new(load_config(cli.config.as_deref())?)?;
+    let profile = cli.profile.clone();
+
+    match &cli.action {
+        Action::List { query, take, skip } => {
+            let options = GetIssues {
+                query: Some(query.clone()),
+                page: Some(Pagination {
+                    skip: Some(*skip),
+                    take: Some(*take),
+                }),
+                profile,
+            };
+            let issues = client.get_issues(&options).await?;
+
+            print(out, cli.format, &issues, || {
+                table(
+                    &["ID", "PROJECT", "SUMMARY"],
+                    issues
+                        .iter()
+                        .map(|issue| {
+                            vec![
+                                issue.text.clone(),
+                                issue.project.name.clone(),
+                                issue.summary.clone(),
+                            ]
+                        })
+                        .collect(),
+                )
+            })
+        }
+        Action::Show { id } => {
+            let options = GetIssue {
+                id: id.clone(),
+                profile,
+            };
+            let issue = client.get_issue(&options).await?;
+
+            print(out, cli.format, &issue, || details(&issue))
+        }
+        Action::Create {
+            project,
+            summary,
+            description,
+        } => {
+            let projects = client
+                .get_projects(&GetProjects {
+                    profile: profile.clone(),
+                })
+                .await?;
+            let project = projects
+                .iter()
+                .find(|candidate| {
+                    candidate.id == *project || candidate.name.eq_ignore_ascii_case(project)
+                })
+                .ok_or_else(|| Error::Str(format!("Project is not found: {}", project)))?;
+
+            let options = CreateIssue {
+                project: project.id.clone(),
+                summary: summary.clone(),
+                description: description.clone(),
+                profile,
+            };
+            let issue = client.create_issue(&options).await?;
+
+            print(out, cli.format, &issue, || {
+                format!("Created {}", issue.text)
+            })
+        }
+        Action::Comment { id, text } => {
+            let options = AddIssueComment {
+                id: id.clone(),
+                comment: text.clone(),
+                profile,
+            };
+            let result = client.add_issue_comment(&options).await?;
+
+            print(out, cli.format, &result, || format!("Commented on {}", id))
+        }
+        Action::Command { id, query } => {
+            let options = ApplyIssueCommand {
+                id: id.clone(),
+                query: query.clone(),
+                profile,
+            };
+            let result = client.apply_issue_command(&options).await?;
+
+            print(out, cli.format, &result, || {
+                format!("Applied to {}: {}", id, query)
+            })
+        }
+    }
+}
+
+/// Prints the value as JSON, or the table rendering of it.
+fn print<T: Serialize>(
+    out: &mut impl Write,
+    format: Format,
+    value: &T,
+    table: impl FnOnce() -> String,
+) -> Result<(), Error> {
+    let output = match format {
+        Format::Json => {
+            serde_json::to_string_pretty(value).map_err(|err| Error::Std(Box::new(err)))?
+        }
+        Format::Table => table(),
+    };
+
+    match writeln!(out, "{}", output) {
+        // the reader has gone away, like `head` that has read enough
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|err| Error::Std(Box::new(err))),
+    }
+}
+
+/// Renders the rows as columns that are padded to their widest cell.
+fn table(header: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+
+    rows.iter().for_each(|row| {
+        row.iter().enumerate().for_each(|(i, cell)| {
+            widths[i] = widths[i].max(cell.chars().count());
+        })
+    });
+
+    std::iter::once(header.iter().map(|h| h.to_string()).collect::<Vec<_>>())
+        .chain(rows)
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:width$}", cell, width = widths[i]))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn details(issue: &Issue) -> String {
+    let mut rows = vec![
+        vec!["ID".to_string(), issue.text.clone()],
+        vec!["Project".to_string(), issue.project.text.clone()],
+        vec!["Summary".to_string(), issue.summary.clone()],
+    ];
+
+    issue.fields.iter().for_each(|field| {
+        rows.push(vec![field.name.clone(), field.text.clone()]);
+    });
+
+    if !issue.tags.is_empty() {
+        rows.push(vec![
+            "Tags".to_string(),
+            issue
+                .tags
+                .iter()
+                .map(|tag| tag.name.clone())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ]);
+    }
+
+    let mut output = table(&["FIELD", "VALUE"], rows);
+
+    if let Some(ref description) = issue.description {
+        output.push_str(&format!("\n\n{}", description));
+    }
+
+    issue.comments.iter().flatten().for_each(|comment| {
+        output.push_str(&format!(
+            "\n\n{} at {}:\n{}",
+            comment.author, comment.created_at, comment.text
+        ));
+    });
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    /// Configuration of the memory backend, whose files are in their own directory.
+    fn config(name: &str, url: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("youtrack-cli-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let fixture = dir.join("fixture.json");
+        std::fs::write(
+            &fixture,
+            json!({
+                "projects": [{ "id": "0-1", "name": "PRJ", "text": "Project" }],
+                "issues": [{
+                    "id": "2-1",
+                    "text": "PRJ-1",
+                    "summary": "First issue",
+                    "description": "Steps to reproduce",
+                    "project": { "id": "0-1", "name": "PRJ", "text": "Project" },
+                    "fields": [{ "id": "1", "name": "State", "text": "Open", "value": { "name": "Open" } }],
+                    "tags": [],
+                    "comments": [],
+                }],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let config = dir.join("config.json");
+        std::fs::write(
+            &config,
+            json!({
+                "url": url,
+                "token": "perm:token",
+                "issues": { "fields": [] },
+                "issue": { "fields": [] },
+                "backend": { "kind": "memory", "fixture": fixture },
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        config
+    }
+
+    fn run(config: &Path, args: &[&str]) -> Result<String, Error> {
+        let config = config.to_string_lossy();
+        let cli = Cli::try_parse_from(
+            ["youtrack", "--config", config.as_ref()]
+                .iter()
+                .chain(args.iter()),
+        )
+        .unwrap();
+        let mut out = vec![];
+
+        RUNTIME.block_on(execute(&cli, &mut out))?;
+
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn arguments_are_parsed() {
+        let cli =
+            Cli::try_parse_from(["youtrack", "list", "--take", "5", "--format", "json"]).unwrap();
+
+        assert_eq!(cli.format, Format::Json);
+        assert!(matches!(
+            cli.action,
+            Action::List { ref query, take: 5, skip: 0 } if query == "for: me #Unresolved"
+        ));
+
+        assert!(Cli::try_parse_from(["youtrack", "show"]).is_err());
+        assert!(Cli::try_parse_from(["youtrack", "list", "--format", "xml"]).is_err());
+    }
+
+    #[test]
+    fn issues_are_printed_as_table_and_json() {
+        let config = config("print", "https://example.com");
+
+        assert_eq!(
+            run(&config, &["list", "first"]).unwrap(),
+            "ID     PROJECT  SUMMARY\nPRJ-1  PRJ      First issue\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&run(&config, &["show", "PRJ-1", "--format", "json"]).unwrap())
+                .unwrap();
+        assert_eq!(json["id"], "2-1");
+        assert_eq!(json["fields"][0]["text"], "Open");
+
+        assert_eq!(
+            run(&config, &["create", "prj", "Second issue"]).unwrap(),
+            "Created PRJ-2\n"
+        );
+        assert_eq!(
+            run(&config, &["list", "second", "--format", "json"]).unwrap(),
+            "[]\n",
+            "the memory backend does not keep the changes between the runs"
+        );
+
+        std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn invalid_configuration_is_rejected() {
+        let config = config("invalid", "not a url");
+
+        assert!(matches!(run(&config, &["list"]), Err(Error::Validation(_))));
+
+        std::fs::remove_dir_all(config.parent().unwrap()).unwrap();
+    }
+}
@@ -1,19 +1,30 @@
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 
+use async_trait::async_trait;
 use chrono::prelude::*;
-use mlua::prelude::*;
-use mlua::{AppDataRef, Lua};
+use percent_encoding::percent_decode_str;
 use serde::{Deserialize, Serialize};
-
-use crate::error::Error;
-use crate::lua::NoData;
-use crate::macros::{from_lua, into_lua};
-use crate::Module;
 use serde_json::{json, Value as JsonValue};
+use url::Url;
 
+use crate::backend::{self, Backend};
+use crate::config::{Config, DEFAULT_PROFILE};
+use crate::error::{ApiError, Error};
+use crate::inflight::InFlight;
+use crate::macros::{from_lua, into_lua};
+use crate::profile::{Profile, Profiles};
+use crate::recorder::Recorder;
+use crate::redact::Redactor;
+
+static API_CONFIG_FIELDS: &str = "version,build";
+static USER_FIELDS: &str = "id,login,fullName,email";
+static TIME_TRACKING_FIELDS: &str = "enabled";
+static HELPDESK_FIELDS: &str = "plugins(helpDeskSettings(enabled))";
 static SAVED_QUERY_FIELDS: &str = "id,name,query";
 static ISSUES_FIELDS: &str = "id,idReadable,summary,description,project(id,name,shortName),customFields(id,name,presentation,value(id,name,presentation,color(background,foreground))),tags(id,color(background,foreground),name)";
 static ISSUE_FIELDS: &str = "id,idReadable,summary,description,project(id,name,shortName),customFields(id,name,presentation,value(id,name,presentation,color(background,foreground))),tags(id,color(background,foreground),name),comments(author(fullName),text,created)";
+static ISSUE_IDS_FIELDS: &str = "id,idReadable";
 static PROJECT_FIELDS: &str = "id,name,shortName";
 static AGILES_FIELDS: &str = "id,name,projects(id,name,shortName),sprints(id,name,isDefault)";
 
@@ -127,108 +138,200 @@ pub struct Field {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GetSavedQueries {
-    pub page: Option<Pagination>,
+pub struct ApiEndpoint {
+    pub url: String,
+
+    pub hub_url: String,
+
+    pub version: Option<String>,
+
+    pub build: Option<String>,
 }
 
-impl Default for GetSavedQueries {
-    fn default() -> Self {
-        GetSavedQueries {
-            page: Some(Pagination::default()),
-        }
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ValidateApi {
+    pub profile: Option<String>,
+}
+
+into_lua!(ValidateApi);
+from_lua!(ValidateApi);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    pub id: String,
+
+    pub login: String,
+
+    pub name: String,
+
+    pub email: Option<String>,
+}
+
+/// Optional features of the server, which are unknown when the user is not permitted to see them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ServerFeatures {
+    pub time_tracking: Option<bool>,
+
+    pub knowledge_base: Option<bool>,
+
+    pub helpdesk: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Connection {
+    pub profile: String,
+
+    pub url: String,
+
+    pub user: User,
+
+    pub version: Option<String>,
+
+    pub build: Option<String>,
+
+    pub features: ServerFeatures,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CheckConnection {
+    pub profile: Option<String>,
+}
+
+into_lua!(CheckConnection);
+from_lua!(CheckConnection);
+
+/// Appends the path to the REST API endpoint, where the path can not leave it, since the token is
+/// sent along. The segments are decoded before they are checked, since servers and proxies can
+/// decode them as well, and encoded again when they are appended.
+fn request_url(api_url: &Url, path: &str) -> Result<Url, Error> {
+    let invalid = || Error::Str(format!("Path should be relative to the REST API: {}", path));
+
+    if Url::parse(path).is_ok() || path.starts_with("//") || path.contains(['?', '#', '\\']) {
+        return Err(invalid());
+    }
+
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| percent_decode_str(s).decode_utf8().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    if segments
+        .iter()
+        .any(|s| s == "." || s == ".." || s.contains(['/', '?', '#', '\\', '%']))
+    {
+        return Err(invalid());
     }
+
+    let mut url = api_url.clone();
+    url.path_segments_mut()
+        .map_err(|_| invalid())?
+        .pop_if_empty()
+        .extend(segments.iter().map(|s| s.as_ref()));
+
+    Ok(url)
 }
 
-into_lua!(GetSavedQueries);
-from_lua!(GetSavedQueries);
+/// Probes the endpoints of the optional features, where a forbidden response means it is unknown.
+async fn get_server_features(c: &Client, p: &Profile) -> Result<ServerFeatures, Error> {
+    let probe = |path: &[&str], query: Vec<(&'static str, &'static str)>| {
+        let mut url = p.api_url.clone();
+        url.path_segments_mut().unwrap().extend(path);
 
-pub type GetSavedQueriesArgs<'lua> = (Option<GetSavedQueries>, LuaFunction<'lua>);
+        p.client.get(url).query(&query)
+    };
 
-#[allow(unused_variables)]
-pub async fn get_saved_queries(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): GetSavedQueriesArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
-
-    url.path_segments_mut().unwrap().push("savedQueries");
-
-    let query: Vec<(&str, JsonValue)> = vec![
-        ("fields", JsonValue::String(SAVED_QUERY_FIELDS.into())),
-        (
-            "$top",
-            JsonValue::Number(
-                options
-                    .clone()
-                    .unwrap_or_default()
-                    .page
-                    .unwrap_or_default()
-                    .take
-                    .unwrap_or_default()
-                    .into(),
+    let mut features = ServerFeatures::default();
+
+    let res = c
+        .send(
+            p,
+            probe(
+                &["admin", "timeTrackingSettings"],
+                vec![("fields", TIME_TRACKING_FIELDS)],
             ),
-        ),
-        (
-            "$skip",
-            JsonValue::Number(
-                options
-                    .clone()
-                    .unwrap_or_default()
-                    .page
-                    .unwrap_or_default()
-                    .skip
-                    .unwrap_or_default()
-                    .into(),
+            true,
+        )
+        .await?;
+    features.time_tracking = match res.status() {
+        reqwest::StatusCode::OK => res
+            .json::<JsonValue>()
+            .await?
+            .get("enabled")
+            .and_then(|v| v.as_bool()),
+        reqwest::StatusCode::NOT_FOUND => Some(false),
+        _ => None,
+    };
+
+    let res = c
+        .send(
+            p,
+            probe(&["articles"], vec![("fields", "id"), ("$top", "1")]),
+            true,
+        )
+        .await?;
+    features.knowledge_base = match res.status() {
+        reqwest::StatusCode::OK => Some(true),
+        reqwest::StatusCode::NOT_FOUND => Some(false),
+        _ => None,
+    };
+
+    let res = c
+        .send(
+            p,
+            probe(
+                &["admin", "projects"],
+                vec![("fields", HELPDESK_FIELDS), ("$top", "-1")],
             ),
+            true,
+        )
+        .await?;
+    features.helpdesk = match res.status() {
+        reqwest::StatusCode::OK => Some(
+            res.json::<JsonValue>()
+                .await?
+                .as_array()
+                .map(|projects| {
+                    projects.iter().any(|project| {
+                        project
+                            .pointer("/plugins/helpDeskSettings/enabled")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false)
+                    })
+                })
+                .unwrap_or(false),
         ),
-    ];
-
-    let req = m.client.get(url).query(&query);
+        // older servers do not know about the helpdesk settings of the projects
+        reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::NOT_FOUND => Some(false),
+        _ => None,
+    };
 
-    log::debug!("Youtrack issue add comment request: {:?}", req);
+    Ok(features)
+}
 
-    let res = req.send().await?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetSavedQueries {
+    pub page: Option<Pagination>,
+    pub profile: Option<String>,
+}
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let result = json
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|query| process_saved_query(query.clone()))
-                .collect::<Result<Vec<SavedQuery>, Error>>()?;
-
-            log::debug!(
-                "Youtrack saved queries fetched: {:?} -> {:#?}",
-                options,
-                result
-            );
-            callback.call((LuaNil, lua.to_value(&result)))?;
-        }
-        _ => {
-            let text = res.text().await?;
-
-            log::debug!(
-                "Youtrack saved queries can not be fetched: {:?} -> {:#?}",
-                options,
-                text,
-            );
-            callback.call((
-                format!("Youtrack saved queries can not be fetched: {:#?}", text),
-                LuaNil,
-            ))?;
+impl Default for GetSavedQueries {
+    fn default() -> Self {
+        GetSavedQueries {
+            page: Some(Pagination::default()),
+            profile: None,
         }
     }
-
-    Ok(NoData)
 }
 
+into_lua!(GetSavedQueries);
+from_lua!(GetSavedQueries);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct GetIssues {
     pub query: Option<String>,
     pub page: Option<Pagination>,
+    pub profile: Option<String>,
 }
 
 impl Default for GetIssues {
@@ -236,6 +339,7 @@ impl Default for GetIssues {
         GetIssues {
             query: Some("for: me #Unresolved".to_string()),
             page: Some(Pagination::default()),
+            profile: None,
         }
     }
 }
@@ -243,512 +347,759 @@ impl Default for GetIssues {
 into_lua!(GetIssues);
 from_lua!(GetIssues);
 
-pub type GetIssuesArgs<'lua> = (Option<GetIssues>, LuaFunction<'lua>);
-
-#[allow(unused_variables)]
-pub async fn get_issues(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): GetIssuesArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
-
-    url.path_segments_mut().unwrap().push("issues");
-
-    let mut query: Vec<(&str, JsonValue)> = vec![
-        ("fields", JsonValue::String(ISSUES_FIELDS.into())),
-        (
-            "query",
-            JsonValue::String(
-                options
-                    .clone()
-                    .unwrap_or_default()
-                    .query
-                    .unwrap_or_default(),
-            ),
-        ),
-        (
-            "$top",
-            JsonValue::Number(
-                options
-                    .clone()
-                    .unwrap_or_default()
-                    .page
-                    .unwrap_or_default()
-                    .take
-                    .unwrap_or_default()
-                    .into(),
-            ),
-        ),
-        (
-            "$skip",
-            JsonValue::Number(
-                options
-                    .clone()
-                    .unwrap_or_default()
-                    .page
-                    .unwrap_or_default()
-                    .skip
-                    .unwrap_or_default()
-                    .into(),
-            ),
-        ),
-    ];
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GetIssue {
+    pub id: String,
+    pub profile: Option<String>,
+}
 
-    m.config.clone().issues.fields.iter().for_each(|field| {
-        query.push(("customFields", JsonValue::String(field.clone())));
-    });
+into_lua!(GetIssue);
+from_lua!(GetIssue);
 
-    let req = m.client.get(url).query(&query);
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateIssue {
+    pub project: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub profile: Option<String>,
+}
 
-    log::debug!("Youtrack issues request: {:?}", req);
+into_lua!(CreateIssue);
+from_lua!(CreateIssue);
 
-    let res = req.send().await?;
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UpdateIssue {
+    pub id: String,
+    pub description: Option<String>,
+    pub summary: Option<String>,
+    pub profile: Option<String>,
+}
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let processed = json
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|issue| process_issue(issue.clone()))
-                .collect::<Result<Vec<Issue>, Error>>()?;
-
-            log::debug!(
-                "Youtrack issues matching: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                processed
-            );
-            callback.call((LuaNil, lua.to_value(&processed)))?;
-        }
-        _ => {
-            let text = res.text().await?;
-
-            log::debug!(
-                "Youtrack issues can not be fetched: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                text
-            );
-            callback.call((
-                format!("Youtrack issues can not be fetched: {:#?}", text),
-                LuaNil,
-            ))?;
-        }
-    }
+into_lua!(UpdateIssue);
+from_lua!(UpdateIssue);
 
-    Ok(NoData)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ApplyIssueCommand {
+    pub id: String,
+    pub query: String,
+    pub profile: Option<String>,
 }
 
+into_lua!(ApplyIssueCommand);
+from_lua!(ApplyIssueCommand);
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GetIssue {
+pub struct AddIssueComment {
     pub id: String,
+    pub comment: String,
+    pub profile: Option<String>,
 }
 
-into_lua!(GetIssue);
-from_lua!(GetIssue);
+into_lua!(AddIssueComment);
+from_lua!(AddIssueComment);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GetProjects {
+    pub profile: Option<String>,
+}
 
-pub type GetIssueArgs<'lua> = (GetIssue, LuaFunction<'lua>);
+into_lua!(GetProjects);
+from_lua!(GetProjects);
 
-#[allow(unused_variables)]
-pub async fn get_issue(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): GetIssueArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GetAgiles {
+    pub profile: Option<String>,
+}
 
-    url.path_segments_mut()
-        .unwrap()
-        .push("issues")
-        .push(options.clone().id.as_str());
+into_lua!(GetAgiles);
+from_lua!(GetAgiles);
 
-    let query: Vec<(&str, JsonValue)> = vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Request {
+    /// HTTP method, defaults to GET.
+    pub method: Option<String>,
+    /// Path relative to the REST API of the profile, like `issues/PRJ-1/comments`, which can not
+    /// leave it even with encoded segments.
+    pub path: String,
+    pub query: Option<BTreeMap<String, JsonValue>>,
+    /// Fields to return, which Youtrack only returns the identifiers of by default.
+    pub fields: Option<String>,
+    pub body: Option<JsonValue>,
+    pub profile: Option<String>,
+}
 
-    let req = m.client.get(url).query(&query);
+into_lua!(Request);
+from_lua!(Request);
+
+/// Client for the configured Youtrack instances, which can be used without Lua.
+///
+/// Every operation runs against the profile of its options, or the active one when it is not set.
+pub struct Client {
+    pub config: Config,
+    pub profiles: Profiles,
+    pub inflight: InFlight,
+    pub recorder: Recorder,
+    pub backend: Box<dyn Backend + Send + Sync>,
+}
 
-    log::debug!("Youtrack issue detail request: {:?}", req);
+impl Client {
+    pub fn new(config: Config) -> Result<Self, Error> {
+        let profiles = Profiles::new(
+            config
+                .profiles()
+                .into_iter()
+                .map(|(name, profile)| Ok((name.clone(), Profile::new(&config, name, profile)?)))
+                .collect::<Result<_, Error>>()?,
+            config
+                .profile
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+        )?;
+
+        let inflight = InFlight::new(config.http.max_concurrent);
+        let recorder = Recorder::load(
+            &config.recorder,
+            Redactor::new(
+                &config.log.redact,
+                config.profiles().values().map(|profile| &profile.token),
+            ),
+        )?;
+        let backend = backend::new(&config.backend)?;
+
+        Ok(Self {
+            config,
+            profiles,
+            inflight,
+            recorder,
+            backend,
+        })
+    }
 
-    let res = req.send().await?;
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, Error> {
+        self.profiles.get(name)
+    }
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let processed = process_issue(json.clone())?;
+    pub async fn validate_api(&self, options: &ValidateApi) -> Result<ApiEndpoint, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-            log::debug!("Youtrack issue details: {:?} -> {:#?}", options, processed);
-            callback.call((LuaNil, lua.to_value(&processed)))?;
-        }
-        _ => {
-            let text = res.text().await?;
-
-            log::debug!(
-                "Youtrack issue details can not be fetched: {:?} -> {:#?}",
-                options,
-                text
-            );
-            callback.call((
-                format!("Youtrack issue details can not be fetched: {:#?}", text),
-                LuaNil,
-            ))?;
-        }
+        self.backend.validate_api(self, p).await
     }
 
-    Ok(NoData)
-}
+    pub async fn check_connection(&self, options: &CheckConnection) -> Result<Connection, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct CreateIssue {
-    pub project: String,
-    pub summary: String,
-    pub description: Option<String>,
-}
+        self.backend.check_connection(self, p).await
+    }
 
-into_lua!(CreateIssue);
-from_lua!(CreateIssue);
+    pub async fn get_saved_queries(
+        &self,
+        options: &GetSavedQueries,
+    ) -> Result<Vec<SavedQuery>, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-pub type CreateIssueArgs<'lua> = (CreateIssue, LuaFunction<'lua>);
+        self.backend.get_saved_queries(self, p, options).await
+    }
 
-#[allow(unused_variables)]
-pub async fn create_issue(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): CreateIssueArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+    pub async fn get_issues(&self, options: &GetIssues) -> Result<Vec<Issue>, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    url.path_segments_mut().unwrap().push("issues");
+        self.backend.get_issues(self, p, options).await
+    }
+
+    pub async fn get_issue(&self, options: &GetIssue) -> Result<Issue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    let query: Vec<(&str, JsonValue)> = vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
+        self.backend.get_issue(self, p, options).await
+    }
 
-    let req = m.client.post(url).query(&query).json(&json!({
-        "project": { "id": options.project },
-        "summary": options.summary,
-        "description": options.description
-    }));
+    pub async fn create_issue(&self, options: &CreateIssue) -> Result<Issue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    log::debug!("Youtrack issue create request: {:?}", req);
+        self.backend.create_issue(self, p, options).await
+    }
 
-    let res = req.send().await?;
+    pub async fn update_issue(&self, options: &UpdateIssue) -> Result<JsonValue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let processed = process_issue(json)?;
-            log::debug!("Youtrack issue created: {:?} -> {:#?}", options, processed);
-            callback.call((LuaNil, lua.to_value(&processed)))?;
-        }
-        _ => {
-            log::debug!(
-                "Youtrack issue can not be created: {:?} -> {:#?}",
-                options,
-                res.text().await?
-            );
-            callback.call(("Youtrack issue issue can not be created.", LuaNil))?;
-        }
+        self.backend.update_issue(self, p, options).await
     }
 
-    Ok(NoData)
-}
+    pub async fn apply_issue_command(
+        &self,
+        options: &ApplyIssueCommand,
+    ) -> Result<JsonValue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct UpdateIssue {
-    pub id: String,
-    pub description: Option<String>,
-    pub summary: Option<String>,
-}
+        self.backend.apply_issue_command(self, p, options).await
+    }
 
-into_lua!(UpdateIssue);
-from_lua!(UpdateIssue);
+    pub async fn add_issue_comment(&self, options: &AddIssueComment) -> Result<JsonValue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-pub type UpdateIssueArgs<'lua> = (UpdateIssue, LuaFunction<'lua>);
+        self.backend.add_issue_comment(self, p, options).await
+    }
 
-#[allow(unused_variables)]
-pub async fn update_issue(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): UpdateIssueArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+    pub async fn get_projects(&self, options: &GetProjects) -> Result<Vec<Project>, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    url.path_segments_mut()
-        .unwrap()
-        .push("issues")
-        .push(options.clone().id.as_str());
+        self.backend.get_projects(self, p, options).await
+    }
 
-    let query: Vec<(&str, JsonValue)> = vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
+    pub async fn get_agiles(&self, options: &GetAgiles) -> Result<Vec<Agile>, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    let req = m.client.post(url).query(&query).json(&json!({
-        "summary": options.summary,
-        "description": options.description
-    }));
+        self.backend.get_agiles(self, p, options).await
+    }
 
-    log::debug!("Youtrack issue update request: {:?}", req);
+    /// Sends a request to an endpoint of the REST API that has no dedicated operation, where an
+    /// empty response is returned as null.
+    pub async fn request(&self, options: &Request) -> Result<JsonValue, Error> {
+        let p = self.profile(options.profile.as_deref())?;
 
-    let res = req.send().await?;
+        let method = match options.method {
+            Some(ref method) => reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| Error::Str(format!("Invalid HTTP method: {}", method)))?,
+            None => reqwest::Method::GET,
+        };
+        let url = request_url(&p.api_url, &options.path)?;
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            log::debug!("Youtrack issue updated: {:?} -> {:#?}", options, json);
-            callback.call((LuaNil, lua.to_value(&json)))?;
+        let mut query: Vec<(&str, JsonValue)> = options
+            .query
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+
+        if let Some(ref fields) = options.fields {
+            query.push(("fields", JsonValue::String(fields.clone())));
         }
-        _ => {
-            log::debug!(
-                "Youtrack issue can not be updated: {:?} -> {:#?}",
-                options,
-                res.text().await?
-            );
-            callback.call((
-                format!("Youtrack issue issue can not be updated: {}", options.id),
-                LuaNil,
-            ))?;
+
+        let mut req = p.client.request(method.clone(), url).query(&query);
+
+        if let Some(ref body) = options.body {
+            req = req.json(body);
         }
-    }
 
-    Ok(NoData)
-}
+        log::debug!("Youtrack request: {:?}", req);
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ApplyIssueCommand {
-    pub id: String,
-    pub query: String,
-}
+        let res = self.send(p, req, method.is_idempotent()).await?;
 
-into_lua!(ApplyIssueCommand);
-from_lua!(ApplyIssueCommand);
+        match res.status() {
+            status if status.is_success() => {
+                let text = res.text().await?;
+
+                if text.is_empty() {
+                    Ok(JsonValue::Null)
+                } else {
+                    serde_json::from_str(&text).map_err(|err| Error::Std(Box::new(err)))
+                }
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack request failed", res).await?,
+            )),
+        }
+    }
+}
 
-pub type ApplyIssueCommandArgs<'lua> = (ApplyIssueCommand, LuaFunction<'lua>);
+/// Backend that talks to the REST API of the Youtrack instance of the profile.
+#[derive(Debug, Default)]
+pub struct HttpBackend;
 
 #[allow(unused_variables)]
-pub async fn apply_issue_command(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): ApplyIssueCommandArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
-
-    url.path_segments_mut().unwrap().push("commands");
-
-    let query: Vec<(&str, JsonValue)> = vec![];
-
-    let req = m.client.post(url).query(&query).json(&json!({
-        "issues": [{ "id": options.id }],
-        "query": options.query
-    }));
-
-    log::debug!("Youtrack issue apply command request: {:?}", req);
-
-    let res = req.send().await?;
-
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            log::debug!(
-                "Youtrack issue command applied: {:?} -> {:#?}",
-                options,
-                json
-            );
-            callback.call((LuaNil, lua.to_value(&json)))?;
-        }
-        _ => {
-            log::debug!(
-                "Youtrack issue command can not be applied: {:?} -> {:#?}",
-                options,
-                res.text().await?
-            );
-            callback.call((
-                format!("Youtrack issue command can not be applied: {}", options.id),
-                LuaNil,
-            ))?;
+#[async_trait]
+impl Backend for HttpBackend {
+    async fn validate_api(&self, c: &Client, p: &Profile) -> Result<ApiEndpoint, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut().unwrap().push("config");
+
+        let query: Vec<(&str, JsonValue)> =
+            vec![("fields", JsonValue::String(API_CONFIG_FIELDS.into()))];
+
+        let req = p.client.get(url).query(&query);
+
+        log::debug!("Youtrack api validation request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+        let status = res.status();
+        let json: Option<JsonValue> = res.json().await.ok();
+
+        // every response of the Youtrack REST API is a json object annotated with its type
+        match json.filter(|json| json.get("$type").is_some()) {
+            Some(json) if status == reqwest::StatusCode::OK => Ok(ApiEndpoint {
+                url: p.api_url.to_string(),
+                hub_url: p.hub_api_url.to_string(),
+                version: json
+                    .get("version")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string()),
+                build: json
+                    .get("build")
+                    .and_then(|v| v.as_str())
+                    .map(|v| v.to_string()),
+            }),
+            json => Err(Error::Api(ApiError {
+                description: json
+                    .and_then(|json| json.get("error_description").cloned())
+                    .and_then(|v| v.as_str().map(|v| v.to_string())),
+                ..ApiError::new(
+                    format!(
+                        "Configured url does not resolve to a Youtrack API: {}",
+                        p.api_url
+                    ),
+                    status,
+                )
+            })),
         }
     }
 
-    Ok(NoData)
-}
+    async fn check_connection(&self, c: &Client, p: &Profile) -> Result<Connection, Error> {
+        let mut url = p.api_url.clone();
+        url.path_segments_mut().unwrap().push("config");
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AddIssueComment {
-    pub id: String,
-    pub comment: String,
-}
+        let req = p.client.get(url).query(&[("fields", API_CONFIG_FIELDS)]);
 
-into_lua!(AddIssueComment);
-from_lua!(AddIssueComment);
+        log::debug!("Youtrack connection check request: {:?}", req);
 
-pub type AddIssueCommentArgs<'lua> = (AddIssueComment, LuaFunction<'lua>);
+        let res = c.send(p, req, true).await?;
 
-#[allow(unused_variables)]
-pub async fn add_issue_comment(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): AddIssueCommentArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+        let config: JsonValue = match res.status() {
+            reqwest::StatusCode::OK => res.json().await?,
+            _ => {
+                return Err(Error::Api(
+                    ApiError::from_response(
+                        format!(
+                            "Youtrack server configuration can not be fetched: {}",
+                            p.api_url
+                        ),
+                        res,
+                    )
+                    .await?,
+                ))
+            }
+        };
 
-    url.path_segments_mut()
-        .unwrap()
-        .push("issues")
-        .push(options.clone().id.as_str())
-        .push("comments");
+        let mut url = p.api_url.clone();
+        url.path_segments_mut().unwrap().push("users").push("me");
+
+        let req = p.client.get(url).query(&[("fields", USER_FIELDS)]);
 
-    let query: Vec<(&str, JsonValue)> = vec![];
+        log::debug!("Youtrack current user request: {:?}", req);
 
-    let req = m.client.post(url).query(&query).json(&json!({
-        "text": options.comment
-    }));
+        let res = c.send(p, req, true).await?;
+
+        let user = match res.status() {
+            reqwest::StatusCode::OK => process_user(res.json().await?)?,
+            _ => {
+                return Err(Error::Api(
+                    ApiError::from_response(
+                        format!("Youtrack token is not authorized for profile: {}", p.name),
+                        res,
+                    )
+                    .await?,
+                ))
+            }
+        };
+
+        Ok(Connection {
+            profile: p.name.clone(),
+            url: p.url.clone(),
+            user,
+            version: config
+                .get("version")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            build: config
+                .get("build")
+                .and_then(|v| v.as_str())
+                .map(|v| v.to_string()),
+            features: get_server_features(c, p).await?,
+        })
+    }
 
-    log::debug!("Youtrack issue add comment request: {:?}", req);
+    async fn get_saved_queries(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetSavedQueries,
+    ) -> Result<Vec<SavedQuery>, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut().unwrap().push("savedQueries");
+
+        let page = options.page.clone().unwrap_or_default();
+        let query: Vec<(&str, JsonValue)> = vec![
+            ("fields", JsonValue::String(SAVED_QUERY_FIELDS.into())),
+            (
+                "$top",
+                JsonValue::Number(page.take.unwrap_or_default().into()),
+            ),
+            (
+                "$skip",
+                JsonValue::Number(page.skip.unwrap_or_default().into()),
+            ),
+        ];
 
-    let res = req.send().await?;
+        let req = p.client.get(url).query(&query);
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            log::debug!("Youtrack issue comment added: {:?} -> {:#?}", options, json);
-            callback.call((LuaNil, lua.to_value(&json)))?;
+        log::debug!("Youtrack saved queries request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let json: JsonValue = res.json().await?;
+
+                json.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|query| process_saved_query(query.clone()))
+                    .collect::<Result<Vec<SavedQuery>, Error>>()
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack saved queries can not be fetched", res).await?,
+            )),
         }
-        _ => {
-            log::debug!(
-                "Youtrack issue comment can not be added: {:?} -> {:#?}",
-                options,
-                res.text().await?
-            );
-            callback.call((
-                format!("Youtrack issue comment can not be added: {}", options.id),
-                LuaNil,
-            ))?;
+    }
+
+    async fn get_issues(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetIssues,
+    ) -> Result<Vec<Issue>, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut().unwrap().push("issues");
+
+        let page = options.page.clone().unwrap_or_default();
+        let mut query: Vec<(&str, JsonValue)> = vec![
+            ("fields", JsonValue::String(ISSUES_FIELDS.into())),
+            (
+                "query",
+                JsonValue::String(options.query.clone().unwrap_or_default()),
+            ),
+            (
+                "$top",
+                JsonValue::Number(page.take.unwrap_or_default().into()),
+            ),
+            (
+                "$skip",
+                JsonValue::Number(page.skip.unwrap_or_default().into()),
+            ),
+        ];
+
+        p.issues.fields.iter().for_each(|field| {
+            query.push(("customFields", JsonValue::String(field.clone())));
+        });
+
+        let req = p.client.get(url).query(&query);
+
+        log::debug!("Youtrack issues request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let json: JsonValue = res.json().await?;
+
+                json.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|issue| process_issue(issue.clone()))
+                    .collect::<Result<Vec<Issue>, Error>>()
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack issues can not be fetched", res).await?,
+            )),
         }
     }
 
-    Ok(NoData)
-}
+    async fn get_issue(&self, c: &Client, p: &Profile, options: &GetIssue) -> Result<Issue, Error> {
+        let mut url = p.api_url.clone();
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct GetProjects {}
+        url.path_segments_mut()
+            .unwrap()
+            .push("issues")
+            .push(options.id.as_str());
 
-into_lua!(GetProjects);
-from_lua!(GetProjects);
+        let query: Vec<(&str, JsonValue)> =
+            vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
 
-pub type GetProjectsArgs<'lua> = (Option<GetProjects>, LuaFunction<'lua>);
+        let req = p.client.get(url).query(&query);
 
-#[allow(unused_variables)]
-pub async fn get_projects(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): GetProjectsArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+        log::debug!("Youtrack issue detail request: {:?}", req);
 
-    url.path_segments_mut()
-        .unwrap()
-        .push("admin")
-        .push("projects");
+        let res = c.send(p, req, true).await?;
 
-    let query: Vec<(&str, JsonValue)> = vec![
-        ("fields", JsonValue::String(PROJECT_FIELDS.into())),
-        ("archived", JsonValue::Bool(false)),
-    ];
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let mut issue = process_issue(res.json().await?)?;
 
-    let req = m.client.get(url).query(&query);
+                // the custom fields can only be limited for the list of issues by the server
+                if !p.issue.fields.is_empty() {
+                    issue
+                        .fields
+                        .retain(|field| p.issue.fields.contains(&field.name));
+                }
 
-    log::debug!("Youtrack projects request: {:?}", req);
+                Ok(issue)
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack issue details can not be fetched", res).await?,
+            )),
+        }
+    }
 
-    let res = req.send().await?;
+    async fn get_issue_ids(
+        &self,
+        c: &Client,
+        p: &Profile,
+        id: &str,
+    ) -> Result<(String, String), Error> {
+        let mut url = p.api_url.clone();
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let processed = json
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|project| process_project(project.clone()))
-                .collect::<Result<Vec<Project>, Error>>()?;
-
-            log::debug!(
-                "Youtrack projects matching: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                processed
-            );
-            callback.call((LuaNil, lua.to_value(&processed)))?;
+        url.path_segments_mut().unwrap().push("issues").push(id);
+
+        let req = p.client.get(url).query(&[("fields", ISSUE_IDS_FIELDS)]);
+
+        log::debug!("Youtrack issue ids request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let json: JsonValue = res.json().await?;
+
+                match (json["id"].as_str(), json["idReadable"].as_str()) {
+                    (Some(id), Some(readable_id)) => Ok((id.to_string(), readable_id.to_string())),
+                    _ => Err(Error::Str(format!(
+                        "Youtrack issue ids are missing: {}",
+                        json
+                    ))),
+                }
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response(
+                    format!("Youtrack issue ids can not be fetched: {}", id),
+                    res,
+                )
+                .await?,
+            )),
         }
-        _ => {
-            let text = res.text().await?;
-
-            log::debug!(
-                "Youtrack projects can not be fetched: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                text
-            );
-            callback.call((
-                format!("Youtrack projects can not be fetched: {:#?}", text),
-                LuaNil,
-            ))?;
+    }
+
+    async fn create_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &CreateIssue,
+    ) -> Result<Issue, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut().unwrap().push("issues");
+
+        let query: Vec<(&str, JsonValue)> =
+            vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
+
+        let req = p.client.post(url).query(&query).json(&json!({
+            "project": { "id": options.project },
+            "summary": options.summary,
+            "description": options.description
+        }));
+
+        log::debug!("Youtrack issue create request: {:?}", req);
+
+        let res = c.send(p, req, false).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => process_issue(res.json().await?),
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack issue can not be created", res).await?,
+            )),
         }
     }
 
-    Ok(NoData)
-}
+    async fn update_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &UpdateIssue,
+    ) -> Result<JsonValue, Error> {
+        let mut url = p.api_url.clone();
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct GetAgiles {}
+        url.path_segments_mut()
+            .unwrap()
+            .push("issues")
+            .push(options.id.as_str());
+
+        let query: Vec<(&str, JsonValue)> =
+            vec![("fields", JsonValue::String(ISSUE_FIELDS.into()))];
+
+        let req = p.client.post(url).query(&query).json(&json!({
+            "summary": options.summary,
+            "description": options.description
+        }));
+
+        log::debug!("Youtrack issue update request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(res.json().await?),
+            _ => Err(Error::Api(
+                ApiError::from_response(
+                    format!("Youtrack issue can not be updated: {}", options.id),
+                    res,
+                )
+                .await?,
+            )),
+        }
+    }
 
-into_lua!(GetAgiles);
-from_lua!(GetAgiles);
+    async fn apply_issue_command(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &ApplyIssueCommand,
+    ) -> Result<JsonValue, Error> {
+        let mut url = p.api_url.clone();
 
-pub type GetAgilesArgs<'lua> = (Option<GetAgiles>, LuaFunction<'lua>);
+        url.path_segments_mut().unwrap().push("commands");
 
-#[allow(unused_variables)]
-pub async fn get_agiles(
-    lua: &Lua,
-    m: AppDataRef<'static, Module>,
-    (options, callback): GetAgilesArgs<'_>,
-) -> Result<NoData, Error> {
-    let mut url = m.api_url.clone();
+        let query: Vec<(&str, JsonValue)> = vec![];
 
-    url.path_segments_mut().unwrap().push("agiles");
+        let req = p.client.post(url).query(&query).json(&json!({
+            "issues": [{ "id": options.id }],
+            "query": options.query
+        }));
 
-    let query: Vec<(&str, JsonValue)> = vec![("fields", JsonValue::String(AGILES_FIELDS.into()))];
+        log::debug!("Youtrack issue apply command request: {:?}", req);
 
-    let req = m.client.get(url).query(&query);
+        let res = c.send(p, req, false).await?;
 
-    log::debug!("Youtrack agiles request: {:?}", req);
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(res.json().await?),
+            _ => Err(Error::Api(
+                ApiError::from_response(
+                    format!("Youtrack issue command can not be applied: {}", options.id),
+                    res,
+                )
+                .await?,
+            )),
+        }
+    }
 
-    let res = req.send().await?;
+    async fn add_issue_comment(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &AddIssueComment,
+    ) -> Result<JsonValue, Error> {
+        let mut url = p.api_url.clone();
 
-    match res.status() {
-        reqwest::StatusCode::OK => {
-            let json: JsonValue = res.json().await?;
-            let processed = json
-                .as_array()
-                .unwrap()
-                .iter()
-                .map(|agile| process_agile(agile.clone()))
-                .collect::<Result<Vec<Agile>, Error>>()?;
-            log::debug!(
-                "Youtrack agiles matching: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                processed
-            );
-            callback.call((LuaNil, lua.to_value(&processed)))?;
+        url.path_segments_mut()
+            .unwrap()
+            .push("issues")
+            .push(options.id.as_str())
+            .push("comments");
+
+        let query: Vec<(&str, JsonValue)> = vec![];
+
+        let req = p.client.post(url).query(&query).json(&json!({
+            "text": options.comment
+        }));
+
+        log::debug!("Youtrack issue add comment request: {:?}", req);
+
+        let res = c.send(p, req, false).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => Ok(res.json().await?),
+            _ => Err(Error::Api(
+                ApiError::from_response(
+                    format!("Youtrack issue comment can not be added: {}", options.id),
+                    res,
+                )
+                .await?,
+            )),
         }
-        _ => {
-            let text = res.text().await?;
-
-            log::debug!(
-                "Youtrack agiles can not be fetched: {:?} -> {:#?}",
-                options.unwrap_or_default(),
-                text
-            );
-            callback.call((
-                format!("Youtrack agiles can not be fetched: {:#?}", text),
-                LuaNil,
-            ))?;
+    }
+
+    async fn get_projects(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetProjects,
+    ) -> Result<Vec<Project>, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut()
+            .unwrap()
+            .push("admin")
+            .push("projects");
+
+        let query: Vec<(&str, JsonValue)> = vec![
+            ("fields", JsonValue::String(PROJECT_FIELDS.into())),
+            ("archived", JsonValue::Bool(false)),
+        ];
+
+        let req = p.client.get(url).query(&query);
+
+        log::debug!("Youtrack projects request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let json: JsonValue = res.json().await?;
+
+                json.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|project| process_project(project.clone()))
+                    .collect::<Result<Vec<Project>, Error>>()
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack projects can not be fetched", res).await?,
+            )),
         }
     }
 
-    Ok(NoData)
+    async fn get_agiles(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetAgiles,
+    ) -> Result<Vec<Agile>, Error> {
+        let mut url = p.api_url.clone();
+
+        url.path_segments_mut().unwrap().push("agiles");
+
+        let query: Vec<(&str, JsonValue)> =
+            vec![("fields", JsonValue::String(AGILES_FIELDS.into()))];
+
+        let req = p.client.get(url).query(&query);
+
+        log::debug!("Youtrack agiles request: {:?}", req);
+
+        let res = c.send(p, req, true).await?;
+
+        match res.status() {
+            reqwest::StatusCode::OK => {
+                let json: JsonValue = res.json().await?;
+
+                json.as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|agile| process_agile(agile.clone()))
+                    .collect::<Result<Vec<Agile>, Error>>()
+            }
+            _ => Err(Error::Api(
+                ApiError::from_response("Youtrack agiles can not be fetched", res).await?,
+            )),
+        }
+    }
 }
 
 fn process_saved_query(query: JsonValue) -> Result<SavedQuery, Error> {
@@ -973,6 +1324,18 @@ fn process_fields(fields: Vec<JsonValue>) -> Result<Vec<Field>, Error> {
     Ok(result)
 }
 
+fn process_user(user: JsonValue) -> Result<User, Error> {
+    Ok(User {
+        id: user.get("id").unwrap().as_str().unwrap().to_string(),
+        login: user.get("login").unwrap().as_str().unwrap().to_string(),
+        name: user.get("fullName").unwrap().as_str().unwrap().to_string(),
+        email: user
+            .get("email")
+            .and_then(|email| email.as_str())
+            .map(|email| email.to_string()),
+    })
+}
+
 fn process_project(project: JsonValue) -> Result<Project, Error> {
     Ok(Project {
         id: project.get("id").unwrap().as_str().unwrap().to_string(),
@@ -1014,3 +1377,77 @@ fn process_agile(agile: JsonValue) -> Result<Agile, Error> {
             .collect::<Result<Vec<Sprint>, Error>>()?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_url_appends_to_api() {
+        let api_url = Url::parse("https://example.com/youtrack/api").unwrap();
+
+        assert_eq!(
+            request_url(&api_url, "/issues/PRJ-1/comments")
+                .unwrap()
+                .as_str(),
+            "https://example.com/youtrack/api/issues/PRJ-1/comments"
+        );
+        assert_eq!(
+            request_url(&api_url, "users/me").unwrap().as_str(),
+            "https://example.com/youtrack/api/users/me"
+        );
+        assert_eq!(
+            request_url(&api_url, "issues/PRJ-1/tags/Needs%20Review")
+                .unwrap()
+                .as_str(),
+            "https://example.com/youtrack/api/issues/PRJ-1/tags/Needs%20Review"
+        );
+    }
+
+    #[test]
+    fn client_can_be_shared_across_tasks() {
+        let config: crate::config::Config = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+            "backend": { "kind": "memory" },
+        }))
+        .unwrap();
+        let client = std::sync::Arc::new(Client::new(config).unwrap());
+
+        let projects = crate::RUNTIME
+            .block_on(
+                crate::RUNTIME
+                    .spawn(async move { client.get_projects(&GetProjects::default()).await }),
+            )
+            .unwrap()
+            .unwrap();
+
+        assert!(projects.is_empty());
+    }
+
+    #[test]
+    fn request_url_stays_in_api() {
+        let api_url = Url::parse("https://example.com/youtrack/api").unwrap();
+
+        [
+            "https://other.com/x",
+            "//other.com/x",
+            "../admin",
+            "issues/../../x",
+            "issues?x=1",
+            "%2e%2e/admin",
+            "issues/%2E%2E/%2e%2e/x",
+            "issues/.%2e/x",
+            "..%2Fadmin",
+            "issues%3Fx=1",
+            "issues%23x",
+            "issues%5C..",
+            "%252e%252e/admin",
+            "issues/%ff",
+        ]
+        .iter()
+        .for_each(|path| assert!(request_url(&api_url, path).is_err(), "{}", path));
+    }
+}
@@ -1,7 +1,13 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+#[cfg(feature = "lua")]
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
+use url::Url;
 use validator::Validate;
 
+use crate::error::Error;
 use crate::macros::{from_lua, into_lua};
 
 #[derive(Debug, Clone, Deserialize, Serialize, Validate)]
@@ -9,12 +15,97 @@ pub struct Config {
     #[validate(url(message = "URL should be a full url of your Youtrack instance."))]
     pub url: String,
 
-    pub token: String,
+    pub token: ConfigToken,
+
+    /// Url of the Hub service when it is not served together with Youtrack.
+    #[validate(url(message = "Hub URL should be a full url of your Hub service."))]
+    pub hub_url: Option<String>,
 
     pub issues: ConfigIssuesIssues,
     pub issue: ConfigIssuesIssue,
+
+    #[serde(default)]
+    pub http: ConfigHttp,
+
+    #[serde(default)]
+    pub retry: ConfigRetry,
+
+    #[serde(default)]
+    pub cache: ConfigCache,
+
+    #[serde(default)]
+    pub offline: ConfigOffline,
+
+    /// Exported functions that cancel their previous in-flight call when called again.
+    #[serde(default)]
+    pub cancel_previous: Vec<String>,
+    #[serde(default)]
+    pub log: ConfigLog,
+    #[serde(default)]
+    pub recorder: ConfigRecorder,
+    #[serde(default)]
+    pub backend: ConfigBackend,
+
+    /// Name of the profile that is active after the setup.
+    pub profile: Option<String>,
+
+    /// Additional Youtrack instances, where the top level configuration is the default profile.
+    #[validate(nested)]
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ConfigProfile>,
 }
 
+pub static DEFAULT_PROFILE: &str = "default";
+
+/// Source of the token, which can be given directly or read from somewhere else.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ConfigToken {
+    Value(String),
+    /// Name of the environment variable that contains the token.
+    Env {
+        env: String,
+    },
+    /// Path of the file that contains the token.
+    File {
+        file: String,
+    },
+    /// Shell command that prints the token to stdout.
+    Command {
+        command: String,
+    },
+}
+
+impl Display for ConfigToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigToken::Value(_) => write!(f, "value"),
+            ConfigToken::Env { env } => write!(f, "environment variable {}", env),
+            ConfigToken::File { file } => write!(f, "file {}", file),
+            ConfigToken::Command { command } => write!(f, "command {}", command),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate)]
+pub struct ConfigProfile {
+    #[validate(url(message = "URL should be a full url of your Youtrack instance."))]
+    pub url: String,
+
+    pub token: ConfigToken,
+
+    #[validate(url(message = "Hub URL should be a full url of your Hub service."))]
+    pub hub_url: Option<String>,
+
+    /// Falls back to the top level configuration when not set.
+    pub issues: Option<ConfigIssuesIssues>,
+
+    /// Falls back to the top level configuration when not set.
+    pub issue: Option<ConfigIssuesIssue>,
+}
+
+from_lua!(ConfigProfile);
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConfigIssuesIssues {
     pub fields: Vec<String>,
@@ -29,8 +120,294 @@ pub struct ConfigIssuesIssue {
 
 from_lua!(ConfigIssuesIssue);
 
-impl Config {}
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigHttp {
+    /// Timeout in milliseconds for establishing the connection.
+    pub connect_timeout: Option<u64>,
+    /// Timeout in milliseconds for the whole request.
+    pub timeout: Option<u64>,
+    /// Proxy url that will be used for both HTTP and HTTPS requests.
+    pub proxy: Option<String>,
+    /// Hosts that should bypass the proxy.
+    pub no_proxy: Vec<String>,
+    /// PEM files with additional root certificates to trust.
+    pub ca_certificates: Vec<String>,
+    pub identity: Option<ConfigHttpIdentity>,
+    /// Disables the certificate verification, should only be used for local testing.
+    pub insecure: bool,
+    /// Maximum amount of requests in flight at once, others wait in the order they were made.
+    pub max_concurrent: Option<usize>,
+}
+
+from_lua!(ConfigHttp);
+
+/// Client certificate for mutual TLS.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfigHttpIdentity {
+    /// PEM file of the client certificate.
+    pub cert: String,
+    /// PEM file of the PKCS#8 private key.
+    pub key: String,
+}
+
+from_lua!(ConfigHttpIdentity);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigRetry {
+    pub enabled: bool,
+    /// Maximum amount of retries after the first attempt.
+    pub attempts: u32,
+    /// Initial delay in milliseconds before the first retry.
+    pub delay: u64,
+    /// Upper bound in milliseconds for a single delay.
+    pub max_delay: u64,
+    pub factor: f64,
+}
+
+impl Default for ConfigRetry {
+    fn default() -> Self {
+        ConfigRetry {
+            enabled: true,
+            attempts: 3,
+            delay: 500,
+            max_delay: 10000,
+            factor: 2.0,
+        }
+    }
+}
+
+from_lua!(ConfigRetry);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigCache {
+    pub enabled: bool,
+    /// Time in seconds per exported function that the response is fresh, others are not cached.
+    pub ttl: BTreeMap<String, u64>,
+    /// Time in seconds after the response gets stale, where it is still served while revalidating.
+    pub stale: u64,
+}
+
+impl Default for ConfigCache {
+    fn default() -> Self {
+        ConfigCache {
+            enabled: true,
+            ttl: BTreeMap::from([
+                ("get_projects".to_string(), 3600),
+                ("get_agiles".to_string(), 3600),
+                ("get_saved_queries".to_string(), 600),
+            ]),
+            stale: 86400,
+        }
+    }
+}
+
+from_lua!(ConfigCache);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigOffline {
+    pub enabled: bool,
+    /// Directory where the responses are persisted, nothing is persisted when not set.
+    pub path: Option<String>,
+    /// Exported functions whose responses are persisted.
+    pub endpoints: Vec<String>,
+    /// Responses that are kept per function, where the least recently stored ones are removed.
+    pub max_entries: usize,
+    /// Queues the mutations that can not be sent while offline to replay them later.
+    pub outbox: bool,
+}
+
+impl Default for ConfigOffline {
+    fn default() -> Self {
+        ConfigOffline {
+            enabled: true,
+            path: None,
+            endpoints: vec![
+                "get_saved_queries".to_string(),
+                "get_issues".to_string(),
+                "get_issue".to_string(),
+                "get_projects".to_string(),
+                "get_agiles".to_string(),
+            ],
+            max_entries: 100,
+            outbox: true,
+        }
+    }
+}
+
+from_lua!(ConfigOffline);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigLog {
+    /// Level of the forwarded records, like `info` or `debug`.
+    pub level: String,
+    /// Levels per target prefix, like `youtrack_nvim::request` or `reqwest`, that override the level.
+    pub targets: BTreeMap<String, String>,
+    /// Writes the records as JSON lines to a file as well, when set.
+    pub file: Option<ConfigLogFile>,
+    pub redact: ConfigRedact,
+}
+
+impl Default for ConfigLog {
+    fn default() -> Self {
+        ConfigLog {
+            level: "info".to_string(),
+            targets: BTreeMap::new(),
+            file: None,
+            redact: ConfigRedact::default(),
+        }
+    }
+}
+
+from_lua!(ConfigLog);
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfigLogFile {
+    /// Has no default, since the plugin defaults it to `youtrack/youtrack.jsonl` in the state
+    /// directory of Neovim.
+    pub path: String,
+    /// Size in bytes after the file is rotated.
+    #[serde(default = "ConfigLogFile::default_max_size")]
+    pub max_size: u64,
+    /// Amount of rotated files that are kept besides the current one.
+    #[serde(default = "ConfigLogFile::default_max_files")]
+    pub max_files: usize,
+}
+
+impl ConfigLogFile {
+    fn default_max_size() -> u64 {
+        5 * 1024 * 1024
+    }
+
+    fn default_max_files() -> usize {
+        3
+    }
+}
+
+from_lua!(ConfigLogFile);
+
+/// Masks the authorization header and tokens in the log records, and optionally personal data.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigRedact {
+    pub enabled: bool,
+    pub emails: bool,
+    /// Summaries, descriptions and comments of issues.
+    pub issue_text: bool,
+}
+
+impl Default for ConfigRedact {
+    fn default() -> Self {
+        ConfigRedact {
+            enabled: true,
+            emails: false,
+            issue_text: false,
+        }
+    }
+}
+
+from_lua!(ConfigRedact);
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecorderMode {
+    #[default]
+    Off,
+    /// Writes every exchange with the server to the recording.
+    Record,
+    /// Serves the responses from the recording instead of sending the requests.
+    Replay,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigRecorder {
+    pub mode: RecorderMode,
+    /// HAR file of the recording.
+    pub path: Option<String>,
+}
+
+from_lua!(ConfigRecorder);
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Http,
+    /// Serves the operations from memory, seeded from the fixture.
+    Memory,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigBackend {
+    pub kind: BackendKind,
+    /// JSON file with the projects, issues, saved queries and agiles of the memory backend.
+    pub fixture: Option<String>,
+}
+
+from_lua!(ConfigBackend);
 
+impl Config {
+    /// Returns all the profiles with the top level configuration as the default profile, where
+    /// unset options are filled from the top level configuration.
+    pub fn profiles(&self) -> BTreeMap<String, ConfigProfile> {
+        let mut profiles = BTreeMap::from([(
+            DEFAULT_PROFILE.to_string(),
+            ConfigProfile {
+                url: self.url.clone(),
+                token: self.token.clone(),
+                hub_url: self.hub_url.clone(),
+                issues: None,
+                issue: None,
+            },
+        )]);
+
+        profiles.extend(self.profiles.clone());
+
+        profiles.values_mut().for_each(|profile| {
+            profile.issues.get_or_insert_with(|| self.issues.clone());
+            profile.issue.get_or_insert_with(|| self.issue.clone());
+        });
+
+        profiles
+    }
+}
+
+impl ConfigProfile {
+    /// Resolves the REST API endpoint, keeping the context path the instance is served under.
+    pub fn api_url(&self) -> Result<Url, Error> {
+        Ok(base_url(self.url.as_str())?.join("api")?)
+    }
+
+    /// Resolves the Hub REST API endpoint, which is embedded in Youtrack unless configured.
+    pub fn hub_api_url(&self) -> Result<Url, Error> {
+        match self.hub_url {
+            Some(ref hub_url) => Ok(base_url(hub_url)?.join("api/rest")?),
+            None => Ok(base_url(self.url.as_str())?.join("hub/api/rest")?),
+        }
+    }
+}
+
+/// Parses the url as a directory so relative joins append to its path instead of replacing it.
+fn base_url(url: &str) -> Result<Url, Error> {
+    let mut url = Url::parse(url)?;
+
+    url.set_query(None);
+    url.set_fragment(None);
+
+    let path = url.path().trim_end_matches('/');
+    let path = path.strip_suffix("/api").unwrap_or(path).to_string();
+    url.set_path(format!("{}/", path).as_str());
+
+    Ok(url)
+}
+
+#[cfg(feature = "lua")]
 impl<'lua> FromLua<'lua> for Config {
     fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
         let c: Config = lua.from_value(value)?;
@@ -43,3 +420,74 @@ impl<'lua> FromLua<'lua> for Config {
 }
 
 into_lua!(Config);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_keeps_the_context_path() {
+        [
+            ("https://example.com", "https://example.com/"),
+            (
+                "https://example.com/youtrack",
+                "https://example.com/youtrack/",
+            ),
+            (
+                "https://example.com/youtrack/",
+                "https://example.com/youtrack/",
+            ),
+            (
+                "https://example.com/youtrack/api/",
+                "https://example.com/youtrack/",
+            ),
+            (
+                "https://example.com/youtrack?x=1#top",
+                "https://example.com/youtrack/",
+            ),
+        ]
+        .iter()
+        .for_each(|(url, expected)| assert_eq!(base_url(url).unwrap().as_str(), *expected));
+    }
+
+    #[test]
+    fn api_urls_are_below_the_context_path() {
+        let profile = ConfigProfile {
+            url: "https://example.com/youtrack".to_string(),
+            token: ConfigToken::Value("perm:token".to_string()),
+            hub_url: None,
+            issues: None,
+            issue: None,
+        };
+
+        assert_eq!(
+            profile.api_url().unwrap().as_str(),
+            "https://example.com/youtrack/api"
+        );
+        assert_eq!(
+            profile.hub_api_url().unwrap().as_str(),
+            "https://example.com/youtrack/hub/api/rest"
+        );
+
+        let profile = ConfigProfile {
+            hub_url: Some("https://hub.example.com/hub".to_string()),
+            ..profile
+        };
+
+        assert_eq!(
+            profile.hub_api_url().unwrap().as_str(),
+            "https://hub.example.com/hub/api/rest"
+        );
+    }
+
+    #[test]
+    fn log_file_needs_a_path() {
+        assert!(serde_json::from_str::<ConfigLogFile>(r#"{ "max_files": 1 }"#).is_err());
+
+        let file: ConfigLogFile =
+            serde_json::from_str(r#"{ "path": "/tmp/youtrack.jsonl" }"#).unwrap();
+
+        assert_eq!(file.max_size, 5 * 1024 * 1024);
+        assert_eq!(file.max_files, 3);
+    }
+}
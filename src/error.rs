@@ -4,7 +4,9 @@ use std::{
 };
 
 use log::SetLoggerError;
-use mlua::prelude::LuaError;
+#[cfg(feature = "lua")]
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub enum Error {
@@ -13,14 +15,134 @@ pub enum Error {
     Std(Box<dyn std::error::Error + Send + Sync>),
     Validation(validator::ValidationErrors),
     HttpClient(reqwest::Error),
-    Api,
+    Api(ApiError),
     Url(url::ParseError),
+    #[cfg(feature = "lua")]
     Lua(mlua::Error),
     Logger(SetLoggerError),
+    /// Error of a request whose response has been shared with identical requests.
+    Shared(Arc<Error>),
 }
 
 impl std::error::Error for Error {}
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiError {
+    pub message: String,
+
+    pub status: u16,
+
+    pub code: Option<String>,
+
+    pub description: Option<String>,
+
+    pub workflow: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ApiErrorBody {
+    error: Option<String>,
+    error_description: Option<String>,
+    error_developer_message: Option<String>,
+    error_workflow_type: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(message: impl Into<String>, status: reqwest::StatusCode) -> Self {
+        Self {
+            message: message.into(),
+            status: status.as_u16(),
+            ..Default::default()
+        }
+    }
+
+    /// Consumes a non successful response and decodes the error body that Youtrack returns.
+    pub async fn from_response(
+        message: impl Into<String>,
+        res: reqwest::Response,
+    ) -> Result<Self, Error> {
+        let mut err = Self::new(message, res.status());
+        let text = res.text().await?;
+
+        let body =
+            serde_json::from_str::<ApiErrorBody>(text.as_str()).unwrap_or_else(|_| ApiErrorBody {
+                error_description: Some(text).filter(|text| !text.is_empty()),
+                ..Default::default()
+            });
+
+        err.workflow = body.error_workflow_type.and(
+            body.error_developer_message
+                .or_else(|| body.error_description.clone()),
+        );
+        err.code = body.error;
+        err.description = body.error_description;
+
+        Ok(err)
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.message, self.status)?;
+
+        if let Some(ref description) = self.description {
+            write!(f, ": {}", description)?;
+        }
+
+        if let Some(ref workflow) = self.workflow {
+            if Some(workflow) != self.description.as_ref() {
+                write!(f, " ({})", workflow)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "lua")]
+impl<'lua> IntoLua<'lua> for ApiError {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        let text = self.to_string();
+        let value = lua.to_value_with(
+            &self,
+            LuaSerializeOptions::new().serialize_none_to_null(false),
+        )?;
+
+        if let LuaValue::Table(ref table) = value {
+            let metatable = lua.create_table()?;
+            metatable.set(
+                "__tostring",
+                lua.create_function(move |_, _: LuaValue| Ok(text.clone()))?,
+            )?;
+            table.set_metatable(Some(metatable));
+        }
+
+        Ok(value)
+    }
+}
+
+impl Error {
+    /// Whether the error means that the server could not be reached at all.
+    pub fn is_offline(&self) -> bool {
+        match self {
+            Error::HttpClient(err) => err.is_connect() || err.is_timeout(),
+            Error::Shared(err) => err.is_offline(),
+            _ => false,
+        }
+    }
+
+    /// Whether the connection could not be established, so the request has not been sent at all.
+    /// Unlike a timeout of the request, after which the server might have applied it already,
+    /// a mutation can be queued then without applying it twice.
+    pub fn is_unreachable(&self) -> bool {
+        match self {
+            Error::HttpClient(err) => err.is_connect(),
+            Error::Shared(err) => err.is_unreachable(),
+            _ => false,
+        }
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Error::*;
@@ -33,10 +155,12 @@ impl Display for Error {
             Std(ref err) => <dyn std::error::Error as fmt::Display>::fmt(&**err, f),
             Validation(ref err) => <validator::ValidationErrors as fmt::Display>::fmt(err, f),
             HttpClient(ref err) => <reqwest::Error as fmt::Display>::fmt(err, f),
-            Api => write!(f, "API returned an unexpected result."),
+            Api(ref err) => <ApiError as fmt::Display>::fmt(err, f),
             Url(ref err) => <url::ParseError as fmt::Display>::fmt(err, f),
+            #[cfg(feature = "lua")]
             Lua(ref err) => <LuaError as fmt::Display>::fmt(err, f),
             Logger(ref err) => <SetLoggerError as fmt::Display>::fmt(err, f),
+            Shared(ref err) => <Error as fmt::Display>::fmt(err, f),
         }
     }
 }
@@ -55,14 +179,11 @@ impl From<validator::ValidationErrors> for Error {
 
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        if err.is_status() {
-            return Self::Str(
-                err.status()
-                    .unwrap()
-                    .canonical_reason()
-                    .unwrap()
-                    .to_string(),
-            );
+        if let Some(status) = err.status() {
+            return Self::Api(ApiError::new(
+                status.canonical_reason().unwrap_or("Unknown"),
+                status,
+            ));
         }
 
         Self::HttpClient(err)
@@ -81,12 +202,14 @@ impl From<SetLoggerError> for Error {
     }
 }
 
+#[cfg(feature = "lua")]
 impl From<LuaError> for Error {
     fn from(err: LuaError) -> Self {
         Self::Lua(err)
     }
 }
 
+#[cfg(feature = "lua")]
 impl From<Error> for mlua::Error {
     fn from(err: Error) -> Self {
         match err {
@@ -95,3 +218,71 @@ impl From<Error> for mlua::Error {
         }
     }
 }
+
+#[cfg(feature = "lua")]
+impl<'lua> IntoLua<'lua> for Error {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        match self {
+            Error::Api(err) => err.into_lua(lua),
+            Error::Shared(err) => match *err {
+                Error::Api(ref err) => err.clone().into_lua(lua),
+                ref err => err.to_string().into_lua(lua),
+            },
+            err => err.to_string().into_lua(lua),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_response(status: u16, body: &str) -> ApiError {
+        let res = http::Response::builder()
+            .status(status)
+            .body(body.to_string())
+            .unwrap();
+
+        crate::RUNTIME
+            .block_on(ApiError::from_response("Request failed", res.into()))
+            .unwrap()
+    }
+
+    #[test]
+    fn from_response_decodes_the_error_body() {
+        let err = from_response(
+            400,
+            r#"{"error": "bad_request", "error_description": "Unknown field: Foo"}"#,
+        );
+
+        assert_eq!(err.status, 400);
+        assert_eq!(err.code.as_deref(), Some("bad_request"));
+        assert_eq!(err.description.as_deref(), Some("Unknown field: Foo"));
+        assert_eq!(err.workflow, None);
+        assert_eq!(err.to_string(), "Request failed [400]: Unknown field: Foo");
+    }
+
+    #[test]
+    fn from_response_keeps_the_workflow_message() {
+        let err = from_response(
+            400,
+            r#"{"error": "bad_request", "error_description": "Rejected", "error_workflow_type": "require", "error_developer_message": "Assignee is required"}"#,
+        );
+
+        assert_eq!(err.workflow.as_deref(), Some("Assignee is required"));
+        assert_eq!(
+            err.to_string(),
+            "Request failed [400]: Rejected (Assignee is required)"
+        );
+    }
+
+    #[test]
+    fn from_response_falls_back_to_the_text() {
+        let err = from_response(502, "Bad Gateway");
+
+        assert_eq!(err.code, None);
+        assert_eq!(err.description.as_deref(), Some("Bad Gateway"));
+
+        assert_eq!(from_response(500, "").description, None);
+    }
+}
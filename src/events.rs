@@ -0,0 +1,226 @@
+use mlua::prelude::*;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+use crate::client::{Client, Issue};
+use crate::profile::Profile;
+
+/// Successful mutations, which are announced to other plugins as `User` autocommands.
+#[derive(Debug, Clone, Copy)]
+pub enum Event {
+    IssueCreated,
+    IssueUpdated,
+    CommandApplied,
+    CommentAdded,
+}
+
+impl Event {
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Event::IssueCreated => "YouTrackIssueCreated",
+            Event::IssueUpdated => "YouTrackIssueUpdated",
+            Event::CommandApplied => "YouTrackCommandApplied",
+            Event::CommentAdded => "YouTrackCommentAdded",
+        }
+    }
+}
+
+/// Both identifiers of the issue, since the callers can change an issue by either of them.
+#[derive(Debug, Clone)]
+pub struct IssueIds {
+    pub id: String,
+    pub readable_id: Option<String>,
+}
+
+impl IssueIds {
+    pub fn new(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            readable_id: Some(issue.text.clone()),
+        }
+    }
+
+    /// Takes the identifiers from the result of the mutation, when it is the changed issue.
+    fn from_result(result: &JsonValue) -> Option<Self> {
+        Some(Self {
+            id: result["id"].as_str()?.to_string(),
+            readable_id: Some(result["idReadable"].as_str()?.to_string()),
+        })
+    }
+
+    /// Takes the identifiers from the result of the mutation, or looks them up otherwise, where
+    /// the given one is kept when they can not be fetched.
+    pub async fn resolve(c: &Client, p: &Profile, id: &str, result: &JsonValue) -> Self {
+        if let Some(ids) = Self::from_result(result) {
+            return ids;
+        }
+
+        match c.backend.get_issue_ids(c, p, id).await {
+            Ok((id, readable_id)) => Self {
+                id,
+                readable_id: Some(readable_id),
+            },
+            Err(err) => {
+                log::debug!("Youtrack issue ids can not be resolved: {} -> {}", id, err);
+
+                Self {
+                    id: id.to_string(),
+                    readable_id: None,
+                }
+            }
+        }
+    }
+}
+
+/// Data of the autocommands, with the ids of the issue, the options of the mutation as the payload
+/// and the response of the server.
+fn data(
+    p: &Profile,
+    ids: &IssueIds,
+    payload: &impl Serialize,
+    result: &impl Serialize,
+) -> JsonValue {
+    json!({
+        "id": ids.id,
+        "readable_id": ids.readable_id,
+        "profile": p.name,
+        "payload": payload,
+        "result": result,
+    })
+}
+
+/// Executes the autocommands of the event. A failing autocommand does not fail the mutation,
+/// since it has been applied already.
+pub fn emit(
+    lua: &Lua,
+    event: Event,
+    p: &Profile,
+    ids: &IssueIds,
+    payload: &impl Serialize,
+    result: &impl Serialize,
+) {
+    let data = data(p, ids, payload, result);
+
+    let emitted = (|| -> LuaResult<()> {
+        let exec: LuaFunction = lua.load("vim.api.nvim_exec_autocmds").eval()?;
+        let options = lua.create_table()?;
+
+        options.set("pattern", event.pattern())?;
+        options.set("modeline", false)?;
+        options.set("data", lua.to_value(&data)?)?;
+
+        exec.call::<_, ()>(("User", options))
+    })();
+
+    match emitted {
+        Ok(_) => log::debug!("Youtrack event emitted: {} -> {}", event.pattern(), ids.id),
+        Err(err) => log::warn!(
+            "Youtrack event can not be emitted: {} -> {}",
+            event.pattern(),
+            err
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn client(name: &str) -> Client {
+        let dir =
+            std::env::temp_dir().join(format!("youtrack-events-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let fixture = dir.join("fixture.json");
+        std::fs::write(
+            &fixture,
+            json!({
+                "issues": [{
+                    "id": "2-7",
+                    "text": "PRJ-1",
+                    "summary": "First issue",
+                    "description": null,
+                    "project": { "id": "0-1", "name": "PRJ", "text": "Project" },
+                    "fields": [],
+                    "tags": [],
+                    "comments": [],
+                }],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let config: Config = serde_json::from_value(json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+            "backend": { "kind": "memory", "fixture": fixture },
+        }))
+        .unwrap();
+        let client = Client::new(config).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        client
+    }
+
+    #[test]
+    fn ids_are_taken_from_the_result() {
+        let c = client("result");
+        let p = c.profile(None).unwrap();
+        // the lookup would not find the issue, so the ids have to come from the result
+        let result = json!({ "id": "2-99", "idReadable": "OTHER-1", "summary": "Updated" });
+
+        let ids = crate::RUNTIME.block_on(IssueIds::resolve(&c, p, "OTHER-1", &result));
+
+        assert_eq!(ids.id, "2-99");
+        assert_eq!(ids.readable_id.as_deref(), Some("OTHER-1"));
+    }
+
+    #[test]
+    fn ids_are_looked_up_by_either_id() {
+        let c = client("lookup");
+        let p = c.profile(None).unwrap();
+        let result = json!({ "$type": "IssueComment" });
+
+        ["PRJ-1", "2-7"].iter().for_each(|id| {
+            let ids = crate::RUNTIME.block_on(IssueIds::resolve(&c, p, id, &result));
+
+            assert_eq!(ids.id, "2-7");
+            assert_eq!(ids.readable_id.as_deref(), Some("PRJ-1"));
+        });
+
+        let ids = crate::RUNTIME.block_on(IssueIds::resolve(&c, p, "PRJ-404", &result));
+
+        assert_eq!(ids.id, "PRJ-404");
+        assert_eq!(ids.readable_id, None);
+    }
+
+    #[test]
+    fn data_has_the_ids_payload_and_result() {
+        let c = client("data");
+        let p = c.profile(None).unwrap();
+        let ids = IssueIds {
+            id: "2-7".to_string(),
+            readable_id: Some("PRJ-1".to_string()),
+        };
+
+        assert_eq!(
+            data(
+                p,
+                &ids,
+                &json!({ "comment": "Done" }),
+                &json!({ "text": "Done" })
+            ),
+            json!({
+                "id": "2-7",
+                "readable_id": "PRJ-1",
+                "profile": "default",
+                "payload": { "comment": "Done" },
+                "result": { "text": "Done" },
+            })
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::future::Future;
+use std::io::{PipeWriter, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+
+use mlua::prelude::*;
+
+use crate::error::Error;
+use crate::writer::LogForwarder;
+
+type Task = Pin<Box<dyn Future<Output = LuaResult<()>>>>;
+
+/// Drives the coroutines of the exported asynchronous functions on the main thread.
+///
+/// Coroutines are only resumed when their future has been woken, which writes to a pipe that the
+/// event loop of Neovim is listening on, instead of polling them on every loop iteration. Log
+/// records from other threads are written out on the same occasion.
+pub struct Executor {
+    tasks: RefCell<HashMap<u64, Task>>,
+    next: Cell<u64>,
+    signal: Arc<Signal>,
+}
+
+/// Tasks that have been woken since the last time the executor ran.
+struct Signal {
+    ready: Mutex<(bool, BTreeSet<u64>)>,
+    writer: Mutex<PipeWriter>,
+}
+
+struct TaskWaker {
+    id: u64,
+    signal: Arc<Signal>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.signal.ready.lock().unwrap().1.insert(self.id);
+        self.signal.notify();
+    }
+}
+
+impl Signal {
+    fn notify(&self) {
+        let result = {
+            let mut ready = self.ready.lock().unwrap();
+
+            // a single byte is enough until the executor runs, it collects every woken task
+            if ready.0 {
+                return;
+            }
+
+            ready.0 = true;
+            self.writer.lock().unwrap().write_all(&[0])
+        };
+
+        if let Err(err) = result {
+            log::error!("Youtrack executor can not be woken up: {}", err);
+        }
+    }
+}
+
+/// Wakes up the executor on the main thread from any thread.
+#[derive(Clone)]
+pub struct Notifier(Arc<Signal>);
+
+impl Notifier {
+    pub fn notify(&self) {
+        self.0.notify();
+    }
+}
+
+impl Executor {
+    /// Creates the pipe and starts listening on it in the event loop, only once per Lua state.
+    pub fn setup(lua: &'static Lua) -> Result<(), Error> {
+        if lua.app_data_ref::<Executor>().is_some() {
+            return Ok(());
+        }
+
+        let (reader, writer) = std::io::pipe().map_err(|err| Error::Std(Box::new(err)))?;
+        let fd = into_fd(reader);
+
+        let run = lua.create_function(|lua: &'static Lua, ()| Executor::run(lua))?;
+
+        lua.load(mlua::chunk! {
+            local pipe = vim.uv.new_pipe(false)
+            pipe:open($fd)
+            pipe:read_start(function(err, data)
+                if err or data then
+                    vim.schedule($run)
+                end
+            end)
+        })
+        .exec()?;
+
+        lua.set_app_data(Executor {
+            tasks: RefCell::new(HashMap::new()),
+            next: Cell::new(0),
+            signal: Arc::new(Signal {
+                ready: Mutex::new((false, BTreeSet::new())),
+                writer: Mutex::new(writer),
+            }),
+        });
+
+        Ok(())
+    }
+
+    pub fn notifier(lua: &Lua) -> Result<Notifier, Error> {
+        let executor = lua.app_data_ref::<Executor>().ok_or(Error::NoSetup)?;
+
+        Ok(Notifier(executor.signal.clone()))
+    }
+
+    /// Starts driving the coroutine, it is polled once right away.
+    pub fn spawn(
+        lua: &'static Lua,
+        task: impl Future<Output = LuaResult<()>> + 'static,
+    ) -> Result<(), Error> {
+        let id = {
+            let executor = lua.app_data_ref::<Executor>().ok_or(Error::NoSetup)?;
+            let id = executor.next.get();
+
+            executor.next.set(id + 1);
+            executor.tasks.borrow_mut().insert(id, Box::pin(task));
+
+            id
+        };
+
+        Ok(Executor::poll(lua, id)?)
+    }
+
+    /// Resumes every task that has been woken since the last run.
+    fn run(lua: &'static Lua) -> LuaResult<()> {
+        let ids = {
+            let executor = lua.app_data_ref::<Executor>().ok_or(Error::NoSetup)?;
+            let mut ready = executor.signal.ready.lock().unwrap();
+            ready.0 = false;
+
+            std::mem::take(&mut ready.1)
+        };
+
+        // every woken task has to be resumed, even if one of them fails
+        let mut result = LogForwarder::drain(lua);
+
+        for id in ids {
+            if let Err(err) = Executor::poll(lua, id) {
+                result = result.and(Err(err));
+            }
+        }
+
+        result.and(LogForwarder::drain(lua))
+    }
+
+    fn poll(lua: &'static Lua, id: u64) -> LuaResult<()> {
+        // the task is taken out while it runs, since it can spawn others
+        let (task, signal) = {
+            let executor = lua.app_data_ref::<Executor>().ok_or(Error::NoSetup)?;
+            let task = executor.tasks.borrow_mut().remove(&id);
+
+            (task, executor.signal.clone())
+        };
+
+        let Some(mut task) = task else {
+            return Ok(());
+        };
+
+        let waker = Waker::from(Arc::new(TaskWaker { id, signal }));
+
+        match task.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(result) => result,
+            Poll::Pending => {
+                if let Some(executor) = lua.app_data_ref::<Executor>() {
+                    executor.tasks.borrow_mut().insert(id, task);
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn into_fd(reader: std::io::PipeReader) -> i32 {
+    use std::os::fd::IntoRawFd;
+
+    reader.into_raw_fd()
+}
+
+#[cfg(windows)]
+fn into_fd(reader: std::io::PipeReader) -> i32 {
+    use std::os::windows::io::IntoRawHandle;
+
+    // libuv expects a C runtime file descriptor on windows
+    unsafe { libc::open_osfhandle(reader.into_raw_handle() as libc::intptr_t, libc::O_RDONLY) }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures_util::future::{AbortHandle, AbortRegistration};
+use mlua::prelude::*;
+
+use crate::Module;
+
+static AWAIT_FUNCTION: &str = "youtrack.await";
+
+/// Handle returned to Lua for every asynchronous call, which allows cancelling the request and
+/// awaiting its result from a coroutine.
+#[derive(Debug, Clone)]
+pub struct RequestHandle {
+    abort: AbortHandle,
+    done: Arc<AtomicBool>,
+    settled: Arc<Mutex<Settled>>,
+}
+
+/// Arguments of the first callback call, and the listeners that are waiting for it.
+#[derive(Debug, Default)]
+struct Settled {
+    result: Option<Vec<LuaRegistryKey>>,
+    listeners: Vec<LuaRegistryKey>,
+}
+
+impl RequestHandle {
+    pub fn new() -> (Self, AbortRegistration) {
+        let (abort, registration) = AbortHandle::new_pair();
+
+        (
+            Self {
+                abort,
+                done: Arc::new(AtomicBool::new(false)),
+                settled: Arc::new(Mutex::new(Settled::default())),
+            },
+            registration,
+        )
+    }
+
+    /// Creates a handle that cancels the same request, but is settled separately, for a result
+    /// that is derived from the one of the request.
+    pub fn derive(&self) -> Self {
+        Self {
+            abort: self.abort.clone(),
+            done: self.done.clone(),
+            settled: Arc::new(Mutex::new(Settled::default())),
+        }
+    }
+
+    pub fn cancel(&self) {
+        if !self.is_done() {
+            self.abort.abort();
+        }
+    }
+
+    pub fn finish(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.abort.is_aborted()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::SeqCst) || self.is_cancelled()
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.settled.lock().unwrap().result.is_some()
+    }
+
+    /// Replaces the trailing callback, which becomes optional, with one that settles the handle
+    /// before calling it.
+    pub fn bind_callback(
+        &self,
+        lua: &'static Lua,
+        args: LuaMultiValue<'static>,
+    ) -> LuaResult<LuaMultiValue<'static>> {
+        let mut args = args.into_vec();
+        let callback = match args.last() {
+            Some(LuaValue::Function(callback)) => Some(callback.clone()),
+            _ => None,
+        };
+
+        if callback.is_some() {
+            args.pop();
+        }
+
+        // asynchronous exports take the options followed by the callback
+        args.resize(1, LuaNil);
+
+        let handle = self.clone();
+        args.push(LuaValue::Function(lua.create_function(
+            move |lua, values: LuaMultiValue| {
+                handle.settle(lua, values.clone())?;
+
+                match callback {
+                    Some(ref callback) => callback.call::<_, ()>(values),
+                    None => Ok(()),
+                }
+            },
+        )?));
+
+        Ok(LuaMultiValue::from_vec(args))
+    }
+
+    /// Stores the result and notifies the listeners, only the first result is kept.
+    pub fn settle<'lua>(&self, lua: &'lua Lua, values: impl IntoLuaMulti<'lua>) -> LuaResult<()> {
+        let values = values.into_lua_multi(lua)?;
+
+        let listeners = {
+            let mut settled = self.settled.lock().unwrap();
+
+            if settled.result.is_some() {
+                return Ok(());
+            }
+
+            settled.result = Some(
+                values
+                    .iter()
+                    .map(|value| lua.create_registry_value(value.clone()))
+                    .collect::<LuaResult<_>>()?,
+            );
+
+            std::mem::take(&mut settled.listeners)
+        };
+
+        for listener in listeners {
+            lua.registry_value::<LuaFunction>(&listener)?
+                .call::<_, ()>(())?;
+            lua.remove_registry_value(listener)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn result<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaMultiValue<'lua>> {
+        let settled = self.settled.lock().unwrap();
+
+        settled
+            .result
+            .iter()
+            .flatten()
+            .map(|key| lua.registry_value::<LuaValue>(key))
+            .collect()
+    }
+
+    pub fn on_done(&self, lua: &Lua, listener: LuaFunction) -> LuaResult<()> {
+        {
+            let mut settled = self.settled.lock().unwrap();
+
+            if settled.result.is_none() {
+                settled.listeners.push(lua.create_registry_value(listener)?);
+
+                return Ok(());
+            }
+        }
+
+        listener.call::<_, ()>(())
+    }
+}
+
+/// Suspends the running coroutine until the awaitable is settled, then returns its result or
+/// raises its error.
+pub fn await_function(lua: &Lua) -> LuaResult<LuaFunction<'_>> {
+    if let Ok(function) = lua.named_registry_value::<LuaFunction>(AWAIT_FUNCTION) {
+        return Ok(function);
+    }
+
+    let function = lua
+        .load(mlua::chunk! {
+            return function(awaitable)
+                if not awaitable:is_settled() then
+                    local co = coroutine.running()
+
+                    if not co then
+                        error("Awaiting is only possible from a coroutine.", 2)
+                    end
+
+                    awaitable:on_done(function()
+                        local ok, err = coroutine.resume(co)
+
+                        if not ok then
+                            error(err, 0)
+                        end
+                    end)
+
+                    coroutine.yield()
+                end
+
+                local err, res, meta = awaitable:result()
+
+                if err ~= nil then
+                    error(err, 0)
+                end
+
+                return res, meta
+            end
+        })
+        .eval::<LuaFunction>()?;
+
+    lua.set_named_registry_value(AWAIT_FUNCTION, function.clone())?;
+
+    Ok(function)
+}
+
+impl LuaUserData for RequestHandle {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        // has to be a Lua function, coroutines can not yield across a Rust function
+        fields.add_field_function_get("await", |lua, _| await_function(lua));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("cancel", |_, this, ()| {
+            this.cancel();
+
+            Ok(())
+        });
+        methods.add_method("is_done", |_, this, ()| Ok(this.is_done()));
+        methods.add_method("is_cancelled", |_, this, ()| Ok(this.is_cancelled()));
+        methods.add_method("is_settled", |_, this, ()| Ok(this.is_settled()));
+        methods.add_method("result", |lua, this, ()| this.result(lua));
+        methods.add_method("on_done", |lua, this, listener: LuaFunction| {
+            this.on_done(lua, listener)
+        });
+    }
+}
+
+/// Keeps track of the in-flight requests per exported function.
+#[derive(Debug, Default)]
+pub struct RequestHandles(Mutex<HashMap<&'static str, RequestHandle>>);
+
+impl Module {
+    /// Registers a new request and cancels the previous in-flight one of the same kind, if it is
+    /// configured to do so.
+    pub fn track_request(&self, name: &'static str, handle: &RequestHandle) {
+        if !self.client.config.cancel_previous.iter().any(|n| n == name) {
+            return;
+        }
+
+        let mut handles = self.requests.0.lock().unwrap();
+
+        if let Some(previous) = handles.insert(name, handle.clone()) {
+            if !previous.is_done() {
+                log::debug!("Cancelling the previous in-flight request: {}", name);
+            }
+
+            previous.cancel();
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use reqwest::{header::HeaderMap, Method, RequestBuilder, Response, StatusCode, Version};
+use tokio::sync::{oneshot, Semaphore, SemaphorePermit};
+
+use crate::error::Error;
+use crate::profile::Profile;
+
+type Waiters = Vec<oneshot::Sender<Result<Snapshot, Arc<Error>>>>;
+
+/// Response that has been read completely, so that it can be handed to every caller that waited
+/// for the same request.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Snapshot {
+    pub fn new(status: StatusCode, headers: HeaderMap, body: Bytes) -> Self {
+        Self {
+            status,
+            version: Version::HTTP_11,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn read(res: Response) -> Result<Self, Error> {
+        Ok(Self {
+            status: res.status(),
+            version: res.version(),
+            headers: res.headers().clone(),
+            body: res.bytes().await?,
+        })
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+
+    pub fn response(&self) -> Response {
+        let mut res = http::Response::new(self.body.clone());
+        *res.status_mut() = self.status;
+        *res.version_mut() = self.version;
+        *res.headers_mut() = self.headers.clone();
+
+        Response::from(res)
+    }
+}
+
+/// Requests that are currently in flight, and the limit on how many of them are sent at once.
+#[derive(Debug)]
+pub struct InFlight {
+    /// Permits are handed out in the order they were asked for, so that a burst of requests can
+    /// not starve the ones that came before it.
+    limit: Option<Semaphore>,
+    pending: Mutex<HashMap<String, Waiters>>,
+}
+
+pub enum Joined<'a> {
+    /// Request is not in flight yet, the caller has to send it and complete the others.
+    Leader(LeaderGuard<'a>),
+    Follower(oneshot::Receiver<Result<Snapshot, Arc<Error>>>),
+}
+
+/// Removes the pending request when the sender is dropped before completing it, for example when
+/// it gets cancelled, so that the waiting callers send the request on their own.
+pub struct LeaderGuard<'a> {
+    inflight: &'a InFlight,
+    key: Option<String>,
+}
+
+impl InFlight {
+    pub fn new(max_concurrent: Option<usize>) -> Self {
+        Self {
+            limit: max_concurrent.filter(|max| *max > 0).map(Semaphore::new),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Waits for a free slot when the amount of concurrent requests is limited.
+    pub async fn permit(&self) -> Option<SemaphorePermit<'_>> {
+        let limit = self.limit.as_ref()?;
+
+        if limit.available_permits() == 0 {
+            log::debug!("Youtrack request is queued until another one completes.");
+        }
+
+        limit.acquire().await.ok()
+    }
+
+    /// Returns the key that identifies identical requests, only reads are coalesced.
+    pub fn key(profile: &Profile, req: &RequestBuilder) -> Option<String> {
+        let req = req.try_clone()?.build().ok()?;
+
+        if req.method() != Method::GET {
+            return None;
+        }
+
+        Some(format!("{} {}", profile.name, req.url()))
+    }
+
+    pub fn join(&self, key: &str) -> Joined<'_> {
+        let mut pending = self.pending.lock().unwrap();
+
+        match pending.get_mut(key) {
+            Some(waiters) => {
+                let (tx, rx) = oneshot::channel();
+                waiters.push(tx);
+
+                Joined::Follower(rx)
+            }
+            None => {
+                pending.insert(key.to_string(), Vec::new());
+
+                Joined::Leader(LeaderGuard {
+                    inflight: self,
+                    key: Some(key.to_string()),
+                })
+            }
+        }
+    }
+}
+
+impl LeaderGuard<'_> {
+    /// Hands the result to every caller that waited for the same request.
+    pub fn complete(mut self, result: Result<Snapshot, Error>) -> Result<Response, Error> {
+        let waiters = self
+            .key
+            .take()
+            .and_then(|key| self.inflight.pending.lock().unwrap().remove(&key))
+            .unwrap_or_default();
+
+        if !waiters.is_empty() {
+            log::debug!(
+                "Youtrack response is shared with {} identical requests.",
+                waiters.len()
+            );
+        }
+
+        match result {
+            Ok(snapshot) => {
+                for tx in waiters {
+                    let _ = tx.send(Ok(snapshot.clone()));
+                }
+
+                Ok(snapshot.response())
+            }
+            Err(err) if waiters.is_empty() => Err(err),
+            Err(err) => {
+                let err = Arc::new(err);
+
+                for tx in waiters {
+                    let _ = tx.send(Err(err.clone()));
+                }
+
+                Err(Error::Shared(err))
+            }
+        }
+    }
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(ref key) = self.key {
+            self.inflight.pending.lock().unwrap().remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(body: &'static str) -> Snapshot {
+        Snapshot::new(StatusCode::OK, HeaderMap::new(), Bytes::from(body))
+    }
+
+    #[test]
+    fn followers_get_the_result_of_the_leader() {
+        let inflight = InFlight::new(None);
+
+        let Joined::Leader(leader) = inflight.join("GET /issues") else {
+            panic!("first request should lead");
+        };
+        let Joined::Follower(mut rx) = inflight.join("GET /issues") else {
+            panic!("identical request should follow");
+        };
+
+        let res = leader.complete(Ok(snapshot("[]"))).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let shared = rx.try_recv().unwrap().unwrap();
+        assert_eq!(shared.body(), &Bytes::from("[]"));
+        assert!(matches!(inflight.join("GET /issues"), Joined::Leader(_)));
+    }
+
+    #[test]
+    fn dropped_leader_frees_the_key() {
+        let inflight = InFlight::new(None);
+
+        let leader = inflight.join("GET /issues");
+        let Joined::Follower(mut rx) = inflight.join("GET /issues") else {
+            panic!("identical request should follow");
+        };
+        drop(leader);
+
+        assert!(rx.try_recv().is_err());
+        assert!(matches!(inflight.join("GET /issues"), Joined::Leader(_)));
+    }
+
+    #[test]
+    fn errors_are_shared_with_the_followers() {
+        let inflight = InFlight::new(None);
+
+        let Joined::Leader(leader) = inflight.join("GET /issues") else {
+            panic!("first request should lead");
+        };
+        let Joined::Follower(mut rx) = inflight.join("GET /issues") else {
+            panic!("identical request should follow");
+        };
+
+        let err = leader
+            .complete(Err(Error::Str("failed".into())))
+            .unwrap_err();
+        let Error::Shared(err) = err else {
+            panic!("error should be shared, got {err:?}");
+        };
+
+        let shared = rx.try_recv().unwrap().unwrap_err();
+        assert!(Arc::ptr_eq(&err, &shared));
+    }
+
+    #[test]
+    fn errors_without_followers_are_not_shared() {
+        let inflight = InFlight::new(None);
+
+        let Joined::Leader(leader) = inflight.join("GET /issues") else {
+            panic!("first request should lead");
+        };
+
+        let err = leader
+            .complete(Err(Error::Str("failed".into())))
+            .unwrap_err();
+        assert!(matches!(err, Error::Str(_)));
+    }
+}
@@ -1,77 +1,139 @@
-use crate::config::Config;
-use client::*;
-use error::Error;
+#[cfg(feature = "lua")]
+use bindings::*;
+#[cfg(feature = "lua")]
+use cache::*;
+pub use client::Client;
+pub use config::Config;
+pub use error::Error;
+#[cfg(feature = "lua")]
+use executor::Executor;
+#[cfg(feature = "lua")]
+use handle::RequestHandles;
+#[cfg(feature = "lua")]
 use lua::NoData;
-use macros::export_async_fn;
+#[cfg(feature = "lua")]
+use macros::{export_async_fn, export_fn};
+#[cfg(feature = "lua")]
 use mlua::prelude::*;
-use reqwest::header::{self, HeaderMap};
-use structured_logger::Builder;
+#[cfg(feature = "lua")]
+use objects::{agile, issue, project};
+#[cfg(feature = "lua")]
+use outbox::*;
+#[cfg(feature = "lua")]
+use profile::*;
+#[cfg(feature = "lua")]
+use redact::Redactor;
 use tokio::runtime::Runtime;
-use url::Url;
-use writer::LuaWriter;
+#[cfg(feature = "lua")]
+use writer::{FileSink, LogFilter, LogForwarder, LogOutput, LuaWriter};
 
-mod client;
-mod config;
-mod error;
+pub mod backend;
+#[cfg(feature = "lua")]
+mod bindings;
+#[cfg(feature = "lua")]
+mod cache;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod client;
+pub mod config;
+pub mod error;
+#[cfg(feature = "lua")]
+mod events;
+#[cfg(feature = "lua")]
+mod executor;
+#[cfg(feature = "lua")]
+mod handle;
+mod inflight;
+#[cfg(feature = "lua")]
 mod lua;
 mod macros;
+pub mod memory;
+#[cfg(feature = "lua")]
+mod objects;
+#[cfg(feature = "lua")]
+mod offline;
+#[cfg(feature = "lua")]
+mod outbox;
+pub mod profile;
+mod recorder;
+mod redact;
+mod request;
+mod token;
+#[cfg(feature = "lua")]
 mod writer;
 
+#[cfg(feature = "lua")]
 struct Module {
-    pub config: Config,
-    pub client: reqwest::Client,
-    pub api_url: Url,
+    pub client: Client,
+    pub requests: RequestHandles,
+    pub cache: ResponseCache,
+    pub outbox: Outbox,
 }
 
+#[cfg(feature = "lua")]
 impl Module {
+    fn new(config: Config) -> Result<Self, Error> {
+        let outbox = Outbox::load(&config);
+
+        Ok(Self {
+            client: Client::new(config)?,
+            requests: RequestHandles::default(),
+            cache: ResponseCache::default(),
+            outbox,
+        })
+    }
+
     fn setup(lua: &'static Lua, config: Config) -> Result<NoData, Error> {
-        let _ = Builder::with_level(log::Level::Trace.as_str())
-            .with_target_writer("*", LuaWriter::new(lua, "youtrack.log")?.get())
-            .try_init()
+        Executor::setup(lua)?;
+
+        let filter = LogFilter::new(&config.log);
+        let max_level = filter.max_level();
+        let (file, file_error) = match config.log.file.as_ref().map(FileSink::open).transpose() {
+            Ok(file) => (file, None),
+            Err(err) => (None, Some(err)),
+        };
+        let redactor = Redactor::new(
+            &config.log.redact,
+            config.profiles().values().map(|profile| &profile.token),
+        );
+        let output = LogOutput::new(filter, redactor, file);
+
+        if lua.app_data_ref::<LogForwarder>().is_some() {
+            LogForwarder::configure(lua, output);
+            log::set_max_level(max_level);
+        } else {
+            let _ = writer::install(
+                LuaWriter::new(lua, "youtrack.log", output)?.get(),
+                max_level,
+            )
             .map(|_| {
                 log::debug!("Setup the logger for the library.");
             });
+        }
 
-        let mut headers = HeaderMap::new();
+        if let Some(err) = file_error {
+            log::warn!("Youtrack log file can not be opened: {}", err);
+        }
 
-        headers.insert(
-            header::AUTHORIZATION,
-            header::HeaderValue::from_str(format!("Bearer {}", config.token).as_str()).map_err(
-                |err| {
-                    log::error!("Failed to create the authorization header: {}", err);
-                    error::Error::NoSetup
-                },
-            )?,
-        );
-
-        let api_url = Url::parse(config.url.as_str())?.join("/api")?;
-
-        let client = reqwest::Client::builder()
-            .user_agent("youtrack-nvim")
-            .default_headers(headers)
-            .build()?;
-        log::debug!("Setup the client with url: {}", api_url);
+        let module = Self::new(config)?;
 
         let guard = RUNTIME.enter();
         lua.set_app_data(guard);
 
-        lua.set_app_data(Self {
-            config,
-            client,
-            api_url,
-        });
+        lua.set_app_data(module);
 
         Ok(NoData {})
     }
 }
 
-static RUNTIME: once_cell::sync::Lazy<Runtime> = once_cell::sync::Lazy::new(|| {
+pub(crate) static RUNTIME: once_cell::sync::Lazy<Runtime> = once_cell::sync::Lazy::new(|| {
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .expect("Failed to create runtime.")
 });
 
+#[cfg(feature = "lua")]
 #[mlua::lua_module(skip_memory_check)]
 pub fn youtrack_lib(lua: &'static Lua) -> mlua::Result<LuaTable<'static>> {
     let exports = lua.create_table()?;
@@ -83,6 +145,19 @@ pub fn youtrack_lib(lua: &'static Lua) -> mlua::Result<LuaTable<'static>> {
         })?,
     )?;
 
+    exports.set("await", handle::await_function(lua)?)?;
+
+    export_fn!(lua, exports, None, get_profiles)?;
+    export_fn!(lua, exports, None, set_profile)?;
+    export_fn!(lua, exports, None, invalidate_cache)?;
+    export_fn!(lua, exports, None, get_outbox)?;
+    export_fn!(lua, exports, None, discard_outbox)?;
+    export_fn!(lua, exports, None, issue)?;
+    export_fn!(lua, exports, None, project)?;
+    export_fn!(lua, exports, None, agile)?;
+
+    export_async_fn!(lua, exports, None, validate_api, ValidateApiArgs)?;
+    export_async_fn!(lua, exports, None, check_connection, CheckConnectionArgs)?;
     export_async_fn!(lua, exports, None, get_saved_queries, GetSavedQueriesArgs)?;
     export_async_fn!(lua, exports, None, get_issues, GetIssuesArgs)?;
     export_async_fn!(lua, exports, None, get_issue, GetIssueArgs)?;
@@ -98,6 +173,10 @@ pub fn youtrack_lib(lua: &'static Lua) -> mlua::Result<LuaTable<'static>> {
     export_async_fn!(lua, exports, None, get_projects, GetProjectsArgs)?;
     export_async_fn!(lua, exports, None, create_issue, CreateIssueArgs)?;
     export_async_fn!(lua, exports, None, get_agiles, GetAgilesArgs)?;
+    export_async_fn!(lua, exports, None, retry_outbox, RetryOutboxArgs)?;
+    export_async_fn!(lua, exports, None, request, RequestArgs)?;
+
+    objects::register(lua, &exports)?;
 
     Ok(exports)
 }
@@ -1,24 +1,37 @@
+#[cfg(feature = "lua")]
 macro_rules! into_lua {
     ($structname: ident) => {
         impl<'lua> mlua::IntoLua<'lua> for $structname {
-            fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
-                lua.to_value(&self)
+            fn into_lua(self, lua: &'lua mlua::Lua) -> mlua::Result<mlua::Value<'lua>> {
+                mlua::LuaSerdeExt::to_value(lua, &self)
             }
         }
     };
 }
 
+/// Types are only converted to Lua values when the library is built as a Lua module.
+#[cfg(not(feature = "lua"))]
+macro_rules! into_lua {
+    ($structname: ident) => {};
+}
+
+#[cfg(feature = "lua")]
 macro_rules! from_lua {
     ($structname: ident) => {
         impl<'lua> mlua::FromLua<'lua> for $structname {
-            fn from_lua(value: LuaValue<'lua>, lua: &'lua Lua) -> LuaResult<Self> {
-                lua.from_value(value)
+            fn from_lua(value: mlua::Value<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<Self> {
+                mlua::LuaSerdeExt::from_value(lua, value)
             }
         }
     };
 }
 
-#[allow(unused_macros)]
+#[cfg(not(feature = "lua"))]
+macro_rules! from_lua {
+    ($structname: ident) => {};
+}
+
+#[cfg(feature = "lua")]
 macro_rules! export_fn {
     ($lua:expr, $exports:expr, $name:expr, $fn:expr) => {
         $exports.set(
@@ -32,40 +45,76 @@ macro_rules! export_fn {
     };
 }
 
+#[cfg(feature = "lua")]
 macro_rules! export_async_fn {
     ($lua:expr, $exports:expr, $name: expr, $fn:expr, $args: ty) => {
         $exports.set(
             $name.unwrap_or(stringify!($fn)),
-            $lua.create_function(move |lua: &'static Lua, args: $args| {
-                let f = $lua
-                    .create_async_function(|lua: &'static Lua, args: $args| async move {
+            $lua.create_function(move |lua: &'static Lua, args: LuaMultiValue<'static>| {
+                let (handle, registration) = $crate::handle::RequestHandle::new();
+
+                lua.app_data_ref::<Module>()
+                    .ok_or_else(|| Error::NoSetup)?
+                    .track_request($name.unwrap_or(stringify!($fn)), &handle);
+
+                let args = <$args as mlua::FromLuaMulti>::from_lua_multi(
+                    handle.bind_callback(lua, args)?,
+                    lua,
+                )?;
+
+                let h = handle.clone();
+                let registration = std::cell::Cell::new(Some(registration));
+                let f = $lua.create_async_function(move |lua: &'static Lua, args: $args| {
+                    let h = h.clone();
+                    let registration = registration.take();
+
+                    async move {
                         let m = lua.app_data_ref::<Module>().ok_or_else(|| Error::NoSetup)?;
+                        let registration = registration.ok_or_else(|| {
+                            Error::Str("Request has already been started.".to_string())
+                        })?;
+
+                        let result =
+                            futures_util::future::Abortable::new($fn(lua, m, args), registration)
+                                .await;
+
+                        h.finish();
 
-                        $fn(lua, m, args).await.map_err(|err| err.into_lua_err())?;
+                        match result {
+                            // callback might not have been called, like for a revalidation
+                            Ok(Ok(_)) => h.settle(lua, (LuaNil, LuaNil))?,
+                            Ok(Err(err)) => {
+                                // settled with the same shape that the callback gets
+                                let err = std::sync::Arc::new(err);
+                                h.settle(lua, (Error::Shared(err.clone()), LuaNil))?;
+
+                                return Err(Error::Shared(err).into_lua_err());
+                            }
+                            Err(_) => {
+                                log::debug!("Request has been cancelled: {}", stringify!($fn));
+
+                                h.settle(lua, ("Request has been cancelled.", LuaNil))?;
+                            }
+                        }
 
                         Ok(LuaValue::Nil)
-                    })?
-                    .bind(args)?;
-
-                lua.load(mlua::chunk! {
-                    local coroutine = coroutine.wrap($f)
-                    local step = function() end
-                    step = function()
-                        if coroutine() ~= nil then
-                            vim.schedule(step)
-                        end
-                    end
-                    step()
-
-                })
-                .exec()?;
-
-                Ok(())
+                    }
+                })?;
+
+                $crate::executor::Executor::spawn(
+                    lua,
+                    lua.create_thread(f)?.into_async::<_, ()>(args),
+                )?;
+
+                Ok(handle)
             })?,
         )
     };
 }
 
+#[cfg(feature = "lua")]
 pub(crate) use export_async_fn;
+#[cfg(feature = "lua")]
+pub(crate) use export_fn;
 pub(crate) use from_lua;
 pub(crate) use into_lua;
@@ -0,0 +1,534 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::backend::Backend;
+use crate::client::{
+    AddIssueComment, Agile, ApiEndpoint, ApplyIssueCommand, Client, Comment, Connection,
+    CreateIssue, GetAgiles, GetIssue, GetIssues, GetProjects, GetSavedQueries, Issue, Pagination,
+    Project, SavedQuery, ServerFeatures, UpdateIssue, User,
+};
+use crate::error::{ApiError, Error};
+use crate::profile::Profile;
+
+/// Type prefix of the internal issue ids, like `2-17`.
+static ISSUE_ID_PREFIX: &str = "2";
+
+/// Content of the memory backend, which is read from the fixture in the same shape that the
+/// operations return.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fixture {
+    pub user: Option<User>,
+
+    pub projects: Vec<Project>,
+
+    pub issues: Vec<Issue>,
+
+    pub saved_queries: Vec<SavedQuery>,
+
+    pub agiles: Vec<Agile>,
+}
+
+/// Backend that serves the operations from memory without a Youtrack instance, where the
+/// mutations only last until the next setup.
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    fixture: Mutex<Fixture>,
+}
+
+impl MemoryBackend {
+    pub fn load(path: Option<&str>) -> Result<Self, Error> {
+        let fixture = match path {
+            Some(path) => {
+                let content = std::fs::read(path).map_err(|err| Error::Std(Box::new(err)))?;
+
+                serde_json::from_slice(&content).map_err(|err| Error::Std(Box::new(err)))?
+            }
+            None => Fixture::default(),
+        };
+
+        log::debug!(
+            "Youtrack memory backend loaded: {:?} -> {} issues",
+            path,
+            fixture.issues.len()
+        );
+
+        Ok(Self {
+            fixture: Mutex::new(fixture),
+        })
+    }
+
+    fn user(&self) -> User {
+        self.fixture
+            .lock()
+            .unwrap()
+            .user
+            .clone()
+            .unwrap_or_else(|| User {
+                id: "1-1".to_string(),
+                login: "memory".to_string(),
+                name: "Memory".to_string(),
+                email: None,
+            })
+    }
+
+    /// Runs the given function on the issue with the readable or internal id.
+    fn with_issue<T>(
+        &self,
+        id: &str,
+        message: impl Into<String>,
+        f: impl FnOnce(&mut Issue) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut fixture = self.fixture.lock().unwrap();
+
+        match fixture
+            .issues
+            .iter_mut()
+            .find(|issue| issue.text == id || issue.id == id)
+        {
+            Some(issue) => f(issue),
+            None => Err(Error::Api(ApiError {
+                description: Some(format!("Issue not found: {}", id)),
+                ..ApiError::new(message, reqwest::StatusCode::NOT_FOUND)
+            })),
+        }
+    }
+}
+
+/// Returns the number that follows the highest one at the end of the ids, like `PRJ-17` or `2-17`.
+fn next_number<'a>(ids: impl Iterator<Item = &'a str>) -> u32 {
+    ids.filter_map(|id| id.rsplit_once('-'))
+        .filter_map(|(_, number)| number.parse::<u32>().ok())
+        .max()
+        .unwrap_or_default()
+        + 1
+}
+
+/// Returns the part of the items that the pagination selects, where a negative take means all.
+fn paginate<T>(items: Vec<T>, page: Option<&Pagination>) -> Vec<T> {
+    let page = page.cloned().unwrap_or_default();
+    let items = items
+        .into_iter()
+        .skip(page.skip.unwrap_or_default().max(0) as usize);
+
+    match page.take.unwrap_or_default() {
+        take if take < 0 => items.collect(),
+        take => items.take(take as usize).collect(),
+    }
+}
+
+/// Matches the words of the query against the text of the issue, where the attribute filters
+/// like `for: me` and the tags like `#Unresolved` are not supported and ignored.
+fn matches(issue: &Issue, query: &str) -> bool {
+    let text = format!(
+        "{} {} {}",
+        issue.text,
+        issue.summary,
+        issue.description.as_deref().unwrap_or_default()
+    )
+    .to_lowercase();
+    let mut words = query.split_whitespace();
+
+    while let Some(word) = words.next() {
+        if word.ends_with(':') {
+            words.next();
+        } else if !word.contains(':')
+            && !word.starts_with('#')
+            && !text.contains(&word.to_lowercase())
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[allow(unused_variables)]
+#[async_trait]
+impl Backend for MemoryBackend {
+    async fn validate_api(&self, c: &Client, p: &Profile) -> Result<ApiEndpoint, Error> {
+        Ok(ApiEndpoint {
+            url: p.api_url.to_string(),
+            hub_url: p.hub_api_url.to_string(),
+            version: None,
+            build: None,
+        })
+    }
+
+    async fn check_connection(&self, c: &Client, p: &Profile) -> Result<Connection, Error> {
+        Ok(Connection {
+            profile: p.name.clone(),
+            url: p.url.clone(),
+            user: self.user(),
+            version: None,
+            build: None,
+            features: ServerFeatures::default(),
+        })
+    }
+
+    async fn get_saved_queries(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetSavedQueries,
+    ) -> Result<Vec<SavedQuery>, Error> {
+        let queries = self.fixture.lock().unwrap().saved_queries.clone();
+
+        Ok(paginate(queries, options.page.as_ref()))
+    }
+
+    async fn get_issues(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetIssues,
+    ) -> Result<Vec<Issue>, Error> {
+        let query = options.query.clone().unwrap_or_default();
+        let issues = self
+            .fixture
+            .lock()
+            .unwrap()
+            .issues
+            .iter()
+            .filter(|issue| matches(issue, &query))
+            .map(|issue| Issue {
+                comments: None,
+                ..issue.clone()
+            })
+            .collect();
+
+        Ok(paginate(issues, options.page.as_ref()))
+    }
+
+    async fn get_issue(&self, c: &Client, p: &Profile, options: &GetIssue) -> Result<Issue, Error> {
+        self.with_issue(
+            &options.id,
+            "Youtrack issue details can not be fetched",
+            |issue| {
+                Ok(Issue {
+                    comments: Some(issue.comments.clone().unwrap_or_default()),
+                    ..issue.clone()
+                })
+            },
+        )
+    }
+
+    async fn create_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &CreateIssue,
+    ) -> Result<Issue, Error> {
+        let mut fixture = self.fixture.lock().unwrap();
+
+        let project = fixture
+            .projects
+            .iter()
+            .find(|project| project.id == options.project || project.name == options.project)
+            .cloned()
+            .ok_or_else(|| {
+                Error::Api(ApiError {
+                    description: Some(format!("Project not found: {}", options.project)),
+                    ..ApiError::new(
+                        "Youtrack issue can not be created",
+                        reqwest::StatusCode::BAD_REQUEST,
+                    )
+                })
+            })?;
+
+        let number = next_number(
+            fixture
+                .issues
+                .iter()
+                .filter(|issue| issue.project.id == project.id)
+                .map(|issue| issue.text.as_str()),
+        );
+        // the internal ids are numbered across all the projects
+        let id = next_number(fixture.issues.iter().map(|issue| issue.id.as_str()));
+
+        let issue = Issue {
+            id: format!("{}-{}", ISSUE_ID_PREFIX, id),
+            text: format!("{}-{}", project.name, number),
+            summary: options.summary.clone(),
+            description: options.description.clone(),
+            project,
+            fields: vec![],
+            tags: vec![],
+            comments: Some(vec![]),
+        };
+
+        fixture.issues.push(issue.clone());
+
+        Ok(issue)
+    }
+
+    async fn update_issue(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &UpdateIssue,
+    ) -> Result<JsonValue, Error> {
+        self.with_issue(
+            &options.id,
+            format!("Youtrack issue can not be updated: {}", options.id),
+            |issue| {
+                if let Some(ref summary) = options.summary {
+                    issue.summary = summary.clone();
+                }
+                if options.description.is_some() {
+                    issue.description = options.description.clone();
+                }
+
+                serde_json::to_value(&*issue).map_err(|err| Error::Std(Box::new(err)))
+            },
+        )
+    }
+
+    /// Applies a command of the form `<field> <value>` or `<field> {<value>}` by setting the value
+    /// of the field.
+    async fn apply_issue_command(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &ApplyIssueCommand,
+    ) -> Result<JsonValue, Error> {
+        let message = format!("Youtrack issue command can not be applied: {}", options.id);
+
+        self.with_issue(&options.id, message.clone(), |issue| {
+            let query = options.query.trim();
+            let field = issue.fields.iter_mut().find_map(|field| {
+                let len = field.name.len();
+                let value = query
+                    .get(..len)
+                    .filter(|name| name.eq_ignore_ascii_case(&field.name))
+                    .and_then(|_| query.get(len..))
+                    .filter(|value| value.starts_with(' '))
+                    .map(|value| value.trim())?;
+                // values with more than one word are quoted in braces
+                let value = value
+                    .strip_prefix('{')
+                    .and_then(|value| value.strip_suffix('}'))
+                    .unwrap_or(value)
+                    .to_string();
+
+                Some((field, value))
+            });
+
+            match field {
+                Some((field, value)) => {
+                    field.value = Some(json!({ "name": value, "text": value }));
+                    field.values = None;
+                    field.text = value;
+
+                    Ok(json!({ "query": options.query }))
+                }
+                None => Err(Error::Api(ApiError {
+                    description: Some(format!("Unknown command: {}", options.query)),
+                    ..ApiError::new(message, reqwest::StatusCode::BAD_REQUEST)
+                })),
+            }
+        })
+    }
+
+    async fn add_issue_comment(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &AddIssueComment,
+    ) -> Result<JsonValue, Error> {
+        let author = self.user().name;
+
+        self.with_issue(
+            &options.id,
+            format!("Youtrack issue comment can not be added: {}", options.id),
+            |issue| {
+                let comment = Comment {
+                    author,
+                    text: options.comment.clone(),
+                    created_at: Local::now().format("%FT%T").to_string(),
+                };
+
+                // comments are ordered from the newest
+                issue
+                    .comments
+                    .get_or_insert_with(Vec::new)
+                    .insert(0, comment.clone());
+
+                serde_json::to_value(comment).map_err(|err| Error::Std(Box::new(err)))
+            },
+        )
+    }
+
+    async fn get_projects(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetProjects,
+    ) -> Result<Vec<Project>, Error> {
+        Ok(self.fixture.lock().unwrap().projects.clone())
+    }
+
+    async fn get_agiles(
+        &self,
+        c: &Client,
+        p: &Profile,
+        options: &GetAgiles,
+    ) -> Result<Vec<Agile>, Error> {
+        Ok(self.fixture.lock().unwrap().agiles.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{BackendKind, Config};
+
+    fn issue(id: &str, text: &str, summary: &str) -> Issue {
+        serde_json::from_value(json!({
+            "id": id,
+            "text": text,
+            "summary": summary,
+            "description": "Steps to reproduce",
+            "project": { "id": "0-1", "name": "PRJ", "text": "Project" },
+            "fields": [{ "id": "1", "name": "State", "text": "Open", "value": { "name": "Open" } }],
+            "tags": [],
+            "comments": [],
+        }))
+        .unwrap()
+    }
+
+    fn backend() -> MemoryBackend {
+        MemoryBackend {
+            fixture: Mutex::new(Fixture {
+                projects: vec![Project {
+                    id: "0-1".to_string(),
+                    name: "PRJ".to_string(),
+                    text: "Project".to_string(),
+                }],
+                issues: vec![
+                    issue("2-1", "PRJ-1", "First issue"),
+                    issue("2-5", "PRJ-2", "Second issue"),
+                ],
+                ..Fixture::default()
+            }),
+        }
+    }
+
+    fn client() -> Client {
+        let mut config: Config = serde_json::from_value(json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+        }))
+        .unwrap();
+        config.backend.kind = BackendKind::Memory;
+
+        Client::new(config).unwrap()
+    }
+
+    #[test]
+    fn paginate_skips_and_takes() {
+        let page = |skip, take| Pagination {
+            skip: Some(skip),
+            take: Some(take),
+        };
+
+        assert_eq!(paginate(vec![1, 2, 3], None), vec![1, 2, 3]);
+        assert_eq!(paginate(vec![1, 2, 3], Some(&page(1, -1))), vec![2, 3]);
+        assert_eq!(paginate(vec![1, 2, 3], Some(&page(1, 1))), vec![2]);
+        assert_eq!(paginate(vec![1, 2, 3], Some(&page(-1, 2))), vec![1, 2]);
+        assert_eq!(
+            paginate(vec![1, 2, 3], Some(&page(5, 2))),
+            Vec::<i32>::new()
+        );
+    }
+
+    #[test]
+    fn matches_words_and_ignores_filters() {
+        let issue = issue("2-1", "PRJ-1", "First issue");
+
+        assert!(matches(&issue, ""));
+        assert!(matches(&issue, "first"));
+        assert!(matches(&issue, "prj-1 REPRODUCE"));
+        assert!(matches(&issue, "for: me #Unresolved state:open first"));
+        assert!(!matches(&issue, "first second"));
+        assert!(!matches(&issue, "for: me second"));
+    }
+
+    #[test]
+    fn create_issue_numbers_the_ids() {
+        let backend = backend();
+        let c = client();
+        let p = c.profile(None).unwrap();
+        let options = CreateIssue {
+            project: "PRJ".to_string(),
+            summary: "Third issue".to_string(),
+            description: None,
+            profile: None,
+        };
+
+        let issue = crate::RUNTIME
+            .block_on(backend.create_issue(&c, p, &options))
+            .unwrap();
+
+        assert_eq!(issue.id, "2-6");
+        assert_eq!(issue.text, "PRJ-3");
+        assert_eq!(issue.project.id, "0-1");
+        assert_eq!(backend.fixture.lock().unwrap().issues.len(), 3);
+
+        let err = crate::RUNTIME
+            .block_on(backend.create_issue(
+                &c,
+                p,
+                &CreateIssue {
+                    project: "OTHER".to_string(),
+                    ..options
+                },
+            ))
+            .unwrap_err();
+
+        assert!(
+            matches!(err, Error::Api(ref err) if err.status == 400),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn apply_issue_command_sets_the_field() {
+        let backend = backend();
+        let c = client();
+        let p = c.profile(None).unwrap();
+        let command = |query: &str| ApplyIssueCommand {
+            id: "PRJ-1".to_string(),
+            query: query.to_string(),
+            profile: None,
+        };
+
+        crate::RUNTIME
+            .block_on(backend.apply_issue_command(&c, p, &command("state {In Progress}")))
+            .unwrap();
+
+        let field = backend.fixture.lock().unwrap().issues[0].fields[0].clone();
+        assert_eq!(field.text, "In Progress");
+        assert_eq!(
+            field.value,
+            Some(json!({ "name": "In Progress", "text": "In Progress" }))
+        );
+
+        // the name of the field has to be followed by the value
+        ["States Done", "State", "Priority Major"]
+            .iter()
+            .for_each(|query| {
+                let result =
+                    crate::RUNTIME.block_on(backend.apply_issue_command(&c, p, &command(query)));
+
+                assert!(result.is_err(), "{}", query);
+            });
+    }
+}
@@ -0,0 +1,525 @@
+use mlua::prelude::*;
+use mlua::AppDataRef;
+
+use crate::client::{
+    AddIssueComment, Agile, ApplyIssueCommand, CreateIssue, GetIssue, GetIssues, Issue, Project,
+};
+use crate::error::Error;
+use crate::handle::RequestHandle;
+use crate::Module;
+
+static EXPORTS: &str = "youtrack.exports";
+
+/// Issue that is handed to Lua as an object, where the fields read like the plain table and the
+/// methods go through the asynchronous exports with the profile that it was fetched with.
+#[derive(Debug, Clone)]
+pub struct IssueObject {
+    issue: Issue,
+    profile: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProjectObject {
+    project: Project,
+    profile: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct AgileObject {
+    agile: Agile,
+    profile: Option<String>,
+}
+
+/// Keeps the exports, so that the methods of the objects go through the same requests, cache and
+/// outbox as the functions.
+pub fn register(lua: &Lua, exports: &LuaTable) -> LuaResult<()> {
+    lua.set_named_registry_value(EXPORTS, exports.clone())
+}
+
+/// Calls an asynchronous export and returns its request handle.
+fn call<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+    options: impl IntoLua<'lua>,
+    callback: Option<LuaFunction<'lua>>,
+) -> LuaResult<RequestHandle> {
+    let exports: LuaTable = lua.named_registry_value(EXPORTS)?;
+    let handle: LuaAnyUserData = exports
+        .get::<_, LuaFunction>(name)?
+        .call((options, callback))?;
+
+    let handle = handle.borrow::<RequestHandle>()?.clone();
+
+    Ok(handle)
+}
+
+/// Calls an asynchronous export, where the returned handle and the callback get what `map` makes
+/// of a successful result instead, or the error when it can not be mapped.
+fn call_mapped<'lua, F>(
+    lua: &'lua Lua,
+    name: &str,
+    options: impl IntoLua<'lua>,
+    callback: Option<LuaFunction<'lua>>,
+    map: F,
+) -> LuaResult<RequestHandle>
+where
+    F: for<'l> Fn(&'l Lua, LuaValue<'l>) -> LuaResult<LuaValue<'l>> + 'static,
+{
+    let name = name.to_string();
+    let request = call(lua, &name, options, None)?;
+    let handle = request.derive();
+    let callback = callback
+        .map(|callback| lua.create_registry_value(callback))
+        .transpose()?;
+
+    // listeners run before the callback and any awaiting coroutine of the request
+    let listener = {
+        let request = request.clone();
+        let handle = handle.clone();
+
+        lua.create_function(move |lua, ()| {
+            let mut values = request.result(lua)?.into_vec();
+            values.resize(2, LuaNil);
+
+            let res = values.pop().unwrap_or(LuaNil);
+            let err = values.pop().unwrap_or(LuaNil);
+            let (err, res) = match (err, res) {
+                (LuaNil, LuaNil) => (LuaNil, LuaNil),
+                (LuaNil, res) => match map(lua, res) {
+                    Ok(res) => (LuaNil, res),
+                    Err(err) => {
+                        log::debug!("Youtrack result of {} can not be mapped: {}", name, err);
+
+                        (Error::Lua(err).into_lua(lua)?, LuaNil)
+                    }
+                },
+                (err, _) => (err, LuaNil),
+            };
+
+            handle.settle(lua, (err.clone(), res.clone()))?;
+
+            match callback {
+                Some(ref callback) => lua
+                    .registry_value::<LuaFunction>(callback)?
+                    .call::<_, ()>((err, res)),
+                None => Ok(()),
+            }
+        })?
+    };
+    request.on_done(lua, listener)?;
+
+    Ok(handle)
+}
+
+/// Handle that is settled already, for the methods that are served without a request.
+fn settled<'lua>(
+    lua: &'lua Lua,
+    values: impl IntoLuaMulti<'lua> + Clone,
+    callback: Option<LuaFunction<'lua>>,
+) -> LuaResult<RequestHandle> {
+    let (handle, _) = RequestHandle::new();
+
+    handle.finish();
+    handle.settle(lua, values.clone())?;
+
+    if let Some(callback) = callback {
+        callback.call::<_, ()>(values)?;
+    }
+
+    Ok(handle)
+}
+
+/// Returns a field of the plain table of the value, for the fields without an object of their own.
+fn field<'lua>(
+    lua: &'lua Lua,
+    value: &impl serde::Serialize,
+    key: &str,
+) -> LuaResult<LuaValue<'lua>> {
+    match lua.to_value(value)? {
+        LuaValue::Table(table) => table.get(key),
+        _ => Ok(LuaNil),
+    }
+}
+
+/// Quotes the value of a field for a command when it has more than one word.
+fn command_value(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("{{{}}}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+impl IssueObject {
+    pub fn new(issue: Issue, profile: Option<String>) -> Self {
+        Self { issue, profile }
+    }
+
+    fn get_options(&self) -> GetIssue {
+        GetIssue {
+            id: self.issue.id.clone(),
+            profile: self.profile.clone(),
+        }
+    }
+
+    fn comment_options(&self, comment: String) -> AddIssueComment {
+        AddIssueComment {
+            id: self.issue.id.clone(),
+            comment,
+            profile: self.profile.clone(),
+        }
+    }
+
+    fn command_options(&self, query: String) -> ApplyIssueCommand {
+        ApplyIssueCommand {
+            id: self.issue.id.clone(),
+            query,
+            profile: self.profile.clone(),
+        }
+    }
+
+    /// Fetches the issue again and updates the object, the handle and the callback get the object.
+    fn refresh<'lua>(
+        lua: &'lua Lua,
+        this: LuaAnyUserData<'lua>,
+        callback: Option<LuaFunction<'lua>>,
+        map: impl for<'l> Fn(&'l Lua, LuaAnyUserData<'l>) -> LuaResult<LuaValue<'l>> + 'static,
+    ) -> LuaResult<RequestHandle> {
+        let options = this.borrow::<IssueObject>()?.get_options();
+        let key = lua.create_registry_value(this)?;
+
+        call_mapped(lua, "get_issue", options, callback, move |lua, res| {
+            let this: LuaAnyUserData = lua.registry_value(&key)?;
+            this.borrow_mut::<IssueObject>()?.issue = lua.from_value(res)?;
+
+            map(lua, this)
+        })
+    }
+
+    fn command<'lua>(
+        lua: &'lua Lua,
+        this: &IssueObject,
+        query: String,
+        callback: Option<LuaFunction<'lua>>,
+    ) -> LuaResult<RequestHandle> {
+        call(
+            lua,
+            "apply_issue_command",
+            this.command_options(query),
+            callback,
+        )
+    }
+}
+
+impl LuaUserData for IssueObject {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Index, |lua, this, key: String| {
+            match key.as_str() {
+                "project" => ProjectObject::new(this.issue.project.clone(), this.profile.clone())
+                    .into_lua(lua),
+                _ => field(lua, &this.issue, &key),
+            }
+        });
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(this.issue.text.clone())
+        });
+
+        methods.add_method("to_table", |lua, this, ()| lua.to_value(&this.issue));
+        methods.add_function(
+            "refresh",
+            |lua, (this, callback): (LuaAnyUserData, Option<LuaFunction>)| {
+                IssueObject::refresh(lua, this, callback, |_, this| Ok(LuaValue::UserData(this)))
+            },
+        );
+        // comments are only part of the details of an issue, not of the lists
+        methods.add_function(
+            "comments",
+            |lua, (this, callback): (LuaAnyUserData, Option<LuaFunction>)| {
+                let comments = this.borrow::<IssueObject>()?.issue.comments.clone();
+
+                match comments {
+                    Some(comments) => settled(lua, (LuaNil, lua.to_value(&comments)?), callback),
+                    None => IssueObject::refresh(lua, this, callback, |lua, this| {
+                        lua.to_value(&this.borrow::<IssueObject>()?.issue.comments)
+                    }),
+                }
+            },
+        );
+        methods.add_function(
+            "comment",
+            |lua, (this, text, callback): (LuaAnyUserData, String, Option<LuaFunction>)| {
+                let options = this.borrow::<IssueObject>()?.comment_options(text);
+                let handle = call(lua, "add_issue_comment", options, callback)?;
+
+                // the loaded comments are stale, they are fetched again on the next call
+                let key = lua.create_registry_value(this)?;
+                let request = handle.clone();
+                handle.on_done(
+                    lua,
+                    lua.create_function(move |lua, ()| {
+                        if let Some(LuaNil) = request.result(lua)?.into_iter().next() {
+                            lua.registry_value::<LuaAnyUserData>(&key)?
+                                .borrow_mut::<IssueObject>()?
+                                .issue
+                                .comments = None;
+                        }
+
+                        Ok(())
+                    })?,
+                )?;
+
+                Ok(handle)
+            },
+        );
+        methods.add_method(
+            "command",
+            |lua, this, (query, callback): (String, Option<LuaFunction>)| {
+                IssueObject::command(lua, this, query, callback)
+            },
+        );
+        methods.add_method(
+            "set_field",
+            |lua, this, (name, value, callback): (String, String, Option<LuaFunction>)| {
+                let query = format!("{} {}", name, command_value(&value));
+
+                IssueObject::command(lua, this, query, callback)
+            },
+        );
+    }
+}
+
+impl ProjectObject {
+    pub fn new(project: Project, profile: Option<String>) -> Self {
+        Self { project, profile }
+    }
+
+    /// Narrows the query down to the issues of the project.
+    fn issues_options(&self, options: Option<GetIssues>) -> GetIssues {
+        let options = options.unwrap_or_default();
+        let query = format!(
+            "project: {{{}}} {}",
+            self.project.name,
+            options.query.as_deref().unwrap_or_default()
+        );
+
+        GetIssues {
+            query: Some(query.trim_end().to_string()),
+            profile: options.profile.or_else(|| self.profile.clone()),
+            ..options
+        }
+    }
+
+    fn create_options(&self, summary: String, description: Option<String>) -> CreateIssue {
+        CreateIssue {
+            project: self.project.id.clone(),
+            summary,
+            description,
+            profile: self.profile.clone(),
+        }
+    }
+}
+
+impl LuaUserData for ProjectObject {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Index, |lua, this, key: String| {
+            field(lua, &this.project, &key)
+        });
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(this.project.text.clone())
+        });
+
+        methods.add_method("to_table", |lua, this, ()| lua.to_value(&this.project));
+        methods.add_method(
+            "issues",
+            |lua, this, (options, callback): (Option<GetIssues>, Option<LuaFunction>)| {
+                let options = this.issues_options(options);
+                let profile = options.profile.clone();
+
+                call_mapped(lua, "get_issues", options, callback, move |lua, res| {
+                    let issues: Vec<Issue> = lua.from_value(res)?;
+
+                    issues
+                        .into_iter()
+                        .map(|issue| IssueObject::new(issue, profile.clone()))
+                        .collect::<Vec<_>>()
+                        .into_lua(lua)
+                })
+            },
+        );
+        methods.add_method(
+            "create_issue",
+            |lua, this, (options, callback): (LuaTable, Option<LuaFunction>)| {
+                let options =
+                    this.create_options(options.get("summary")?, options.get("description")?);
+                let profile = this.profile.clone();
+
+                call_mapped(lua, "create_issue", options, callback, move |lua, res| {
+                    IssueObject::new(lua.from_value(res)?, profile.clone()).into_lua(lua)
+                })
+            },
+        );
+    }
+}
+
+impl AgileObject {
+    pub fn new(agile: Agile, profile: Option<String>) -> Self {
+        Self { agile, profile }
+    }
+
+    fn projects(&self) -> Vec<ProjectObject> {
+        self.agile
+            .projects
+            .iter()
+            .map(|project| ProjectObject::new(project.clone(), self.profile.clone()))
+            .collect()
+    }
+}
+
+impl LuaUserData for AgileObject {
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Index, |lua, this, key: String| {
+            field(lua, &this.agile, &key)
+        });
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(this.agile.name.clone())
+        });
+
+        methods.add_method("to_table", |lua, this, ()| lua.to_value(&this.agile));
+        methods.add_method("projects", |_, this, ()| Ok(this.projects()));
+    }
+}
+
+pub type IssueArgs<'lua> = (Issue, Option<String>);
+
+/// Wraps an issue from the exports, like the results of `get_issues`, into an object.
+#[allow(unused_variables)]
+pub fn issue(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (issue, profile): IssueArgs,
+) -> Result<IssueObject, Error> {
+    Ok(IssueObject::new(issue, profile))
+}
+
+pub type ProjectArgs<'lua> = (LuaValue<'lua>, Option<String>);
+
+#[allow(unused_variables)]
+pub fn project(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (project, profile): ProjectArgs,
+) -> Result<ProjectObject, Error> {
+    Ok(ProjectObject::new(lua.from_value(project)?, profile))
+}
+
+pub type AgileArgs<'lua> = (LuaValue<'lua>, Option<String>);
+
+#[allow(unused_variables)]
+pub fn agile(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (agile, profile): AgileArgs,
+) -> Result<AgileObject, Error> {
+    Ok(AgileObject::new(lua.from_value(agile)?, profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn project() -> Project {
+        serde_json::from_value(json!({"id": "0-1", "name": "PRJ", "text": "Project"})).unwrap()
+    }
+
+    fn issue() -> IssueObject {
+        let issue = serde_json::from_value(json!({
+            "id": "PRJ-1",
+            "text": "PRJ-1 First issue",
+            "summary": "First issue",
+            "description": null,
+            "project": project(),
+            "fields": [],
+            "tags": [],
+            "comments": null,
+        }))
+        .unwrap();
+
+        IssueObject::new(issue, Some("work".to_string()))
+    }
+
+    #[test]
+    fn issue_methods_use_the_profile_of_the_issue() {
+        let issue = issue();
+
+        let options = issue.get_options();
+        assert_eq!(options.id, "PRJ-1");
+        assert_eq!(options.profile.as_deref(), Some("work"));
+
+        let options = issue.comment_options("Done".to_string());
+        assert_eq!(options.id, "PRJ-1");
+        assert_eq!(options.comment, "Done");
+        assert_eq!(options.profile.as_deref(), Some("work"));
+
+        let options = issue.command_options("State Fixed".to_string());
+        assert_eq!(options.id, "PRJ-1");
+        assert_eq!(options.query, "State Fixed");
+        assert_eq!(options.profile.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn field_values_are_quoted_for_commands() {
+        assert_eq!(command_value("Fixed"), "Fixed");
+        assert_eq!(command_value("In Progress"), "{In Progress}");
+    }
+
+    #[test]
+    fn project_issues_are_narrowed_to_the_project() {
+        let project = ProjectObject::new(project(), Some("work".to_string()));
+
+        let options = project.issues_options(None);
+        assert_eq!(
+            options.query.as_deref(),
+            Some("project: {PRJ} for: me #Unresolved")
+        );
+        assert_eq!(options.profile.as_deref(), Some("work"));
+
+        let options = project.issues_options(Some(GetIssues {
+            query: None,
+            page: None,
+            profile: Some("other".to_string()),
+        }));
+        assert_eq!(options.query.as_deref(), Some("project: {PRJ}"));
+        assert!(options.page.is_none());
+        assert_eq!(options.profile.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn project_creates_issues_in_the_project() {
+        let project = ProjectObject::new(project(), None);
+
+        let options = project.create_options("New issue".to_string(), None);
+        assert_eq!(options.project, "0-1");
+        assert_eq!(options.summary, "New issue");
+        assert!(options.description.is_none());
+        assert!(options.profile.is_none());
+    }
+
+    #[test]
+    fn agile_projects_keep_the_profile() {
+        let agile = serde_json::from_value(json!({
+            "id": "1-1",
+            "name": "Board",
+            "projects": [project()],
+            "sprints": [],
+        }))
+        .unwrap();
+        let agile = AgileObject::new(agile, Some("work".to_string()));
+
+        let projects = agile.projects();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project.id, "0-1");
+        assert_eq!(projects[0].profile.as_deref(), Some("work"));
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::prelude::*;
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use crate::cache::CacheKey;
+use crate::error::Error;
+use crate::lua::NoData;
+use crate::macros::into_lua;
+use crate::Module;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OfflineEntry {
+    fetched_at: DateTime<Utc>,
+    value: JsonValue,
+}
+
+/// Passed as the third argument of the callback when the response is served from the disk.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OfflineMeta {
+    pub offline: bool,
+
+    pub fetched_at: String,
+}
+
+into_lua!(OfflineMeta);
+
+impl Module {
+    fn offline_path(&self, key: &CacheKey) -> Option<PathBuf> {
+        let offline = &self.client.config.offline;
+
+        if !offline.enabled || !offline.endpoints.iter().any(|e| e == key.endpoint) {
+            return None;
+        }
+
+        let path = offline.path.as_ref()?;
+
+        Some(
+            PathBuf::from(path)
+                .join(key.profile.as_str())
+                .join(key.endpoint)
+                .join(format!("{:016x}.json", fnv1a(key.options.as_bytes()))),
+        )
+    }
+
+    /// Persists the response, so that it can be served when the server is not reachable.
+    pub async fn store_offline<T: Serialize>(&self, key: &CacheKey, value: &T) {
+        let Some(path) = self.offline_path(key) else {
+            return;
+        };
+
+        let result = async {
+            let entry = serde_json::to_vec(&OfflineEntry {
+                fetched_at: Utc::now(),
+                value: serde_json::to_value(value).map_err(|err| Error::Std(Box::new(err)))?,
+            })
+            .map_err(|err| Error::Std(Box::new(err)))?;
+
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|err| Error::Std(Box::new(err)))?;
+            }
+
+            tokio::fs::write(&path, entry)
+                .await
+                .map_err(|err| Error::Std(Box::new(err)))?;
+
+            // every query and option set has its own file, so the oldest ones are removed
+            match path.parent() {
+                Some(parent) => prune(parent, self.client.config.offline.max_entries)
+                    .await
+                    .map_err(|err| Error::Std(Box::new(err))),
+                None => Ok(()),
+            }
+        }
+        .await;
+
+        if let Err(err) = result {
+            log::warn!(
+                "Youtrack response can not be stored for offline use: {} -> {}",
+                path.display(),
+                err
+            );
+        }
+    }
+
+    /// Calls the callback with the persisted response, or returns the original error when there
+    /// is nothing stored for the request.
+    pub async fn serve_offline(
+        &self,
+        lua: &Lua,
+        key: &CacheKey,
+        callback: LuaFunction<'_>,
+        err: Error,
+    ) -> Result<NoData, Error> {
+        let Some(path) = self.offline_path(key) else {
+            return Err(err);
+        };
+
+        let Some(entry) = tokio::fs::read(&path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_slice::<OfflineEntry>(&content).ok())
+        else {
+            return Err(err);
+        };
+
+        log::debug!(
+            "Youtrack is not reachable, serving from offline storage: {} -> {}",
+            path.display(),
+            err
+        );
+
+        let meta = OfflineMeta {
+            offline: true,
+            fetched_at: entry
+                .fetched_at
+                .with_timezone(&Local)
+                .format("%FT%T")
+                .to_string(),
+        };
+
+        callback.call::<_, ()>((LuaNil, lua.to_value(&entry.value)?, meta))?;
+
+        Ok(NoData)
+    }
+}
+
+/// Removes the least recently stored responses of the directory above the limit.
+async fn prune(dir: &Path, max_entries: usize) -> Result<(), std::io::Error> {
+    let mut files: Vec<(SystemTime, PathBuf)> = vec![];
+    let mut entries = tokio::fs::read_dir(dir).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push((entry.metadata().await?.modified()?, path));
+        }
+    }
+
+    if files.len() <= max_entries {
+        return Ok(());
+    }
+
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (_, path) in files.into_iter().skip(max_entries) {
+        log::debug!("Youtrack offline response is pruned: {}", path.display());
+
+        match tokio::fs::remove_file(&path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Stable hash for the file names, since the std hasher can change between releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn prune_keeps_the_most_recent_responses() {
+        let dir = std::env::temp_dir().join(format!("youtrack-offline-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let now = SystemTime::now();
+        (0..5).for_each(|i| {
+            let file = std::fs::File::create(dir.join(format!("{}.json", i))).unwrap();
+            file.set_modified(now - Duration::from_secs(60 * (5 - i)))
+                .unwrap();
+        });
+        std::fs::write(dir.join("notes.txt"), "kept").unwrap();
+
+        crate::RUNTIME.block_on(prune(&dir, 2)).unwrap();
+
+        let mut files = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+
+        assert_eq!(files, vec!["3.json", "4.json", "notes.txt"]);
+
+        crate::RUNTIME.block_on(prune(&dir, 5)).unwrap();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,574 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use chrono::prelude::*;
+use mlua::prelude::*;
+use mlua::{AppDataRef, Lua};
+use serde::{Deserialize, Serialize};
+
+use serde_json::Value as JsonValue;
+
+use crate::client::{AddIssueComment, ApplyIssueCommand, Client, UpdateIssue};
+use crate::config::Config;
+use crate::error::Error;
+use crate::events::{self, Event, IssueIds};
+use crate::lua::NoData;
+use crate::macros::{from_lua, into_lua};
+use crate::profile::Profile;
+use crate::Module;
+
+static OUTBOX_FILE: &str = "outbox.json";
+
+/// Mutation that could not be sent because the server was not reachable.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutboxOperation {
+    UpdateIssue(UpdateIssue),
+    ApplyIssueCommand(ApplyIssueCommand),
+    AddIssueComment(AddIssueComment),
+}
+
+impl OutboxOperation {
+    async fn apply(&self, c: &Client, p: &Profile) -> Result<JsonValue, Error> {
+        match self {
+            OutboxOperation::UpdateIssue(options) => c.backend.update_issue(c, p, options).await,
+            OutboxOperation::ApplyIssueCommand(options) => {
+                c.backend.apply_issue_command(c, p, options).await
+            }
+            OutboxOperation::AddIssueComment(options) => {
+                c.backend.add_issue_comment(c, p, options).await
+            }
+        }
+    }
+
+    /// Announces the replayed mutation the same way as when it is sent right away.
+    async fn emit(&self, lua: &Lua, c: &Client, p: &Profile, result: &JsonValue) {
+        let (event, id, payload): (Event, &str, &dyn erased_serde::Serialize) = match self {
+            OutboxOperation::UpdateIssue(options) => (Event::IssueUpdated, &options.id, options),
+            OutboxOperation::ApplyIssueCommand(options) => {
+                (Event::CommandApplied, &options.id, options)
+            }
+            OutboxOperation::AddIssueComment(options) => {
+                (Event::CommentAdded, &options.id, options)
+            }
+        };
+        let ids = IssueIds::resolve(c, p, id, result).await;
+
+        events::emit(lua, event, p, &ids, &payload, result);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboxEntry {
+    pub id: u64,
+
+    pub profile: String,
+
+    pub operation: OutboxOperation,
+
+    pub queued_at: String,
+
+    pub attempts: u32,
+
+    pub error: Option<String>,
+}
+
+into_lua!(OutboxEntry);
+
+/// Passed as the third argument of the callback when the mutation has been queued.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutboxMeta {
+    pub offline: bool,
+
+    pub queued: bool,
+
+    pub id: u64,
+}
+
+into_lua!(OutboxMeta);
+
+/// Queued mutations, which are persisted next to the offline responses.
+#[derive(Debug, Default)]
+pub struct Outbox {
+    path: Option<PathBuf>,
+    entries: Mutex<Vec<OutboxEntry>>,
+    replaying: AtomicBool,
+}
+
+/// Marks the replay as in progress until it is dropped, also when the replay is cancelled.
+struct ReplayGuard<'a>(&'a AtomicBool);
+
+impl Drop for ReplayGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Outbox {
+    pub fn load(config: &Config) -> Self {
+        let path = config
+            .offline
+            .path
+            .as_ref()
+            .filter(|_| config.offline.enabled && config.offline.outbox)
+            .map(|path| PathBuf::from(path).join(OUTBOX_FILE));
+
+        let entries = path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|content| {
+                serde_json::from_slice::<Vec<OutboxEntry>>(&content)
+                    .map_err(|err| {
+                        log::warn!("Youtrack outbox can not be read: {}", err);
+                    })
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        if !entries.is_empty() {
+            log::debug!("Youtrack outbox loaded with {} entries.", entries.len());
+        }
+
+        Self {
+            path,
+            entries: Mutex::new(entries),
+            replaying: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Starts a replay unless one is in progress already, since both would send the same entries.
+    fn start_replay(&self) -> Option<ReplayGuard<'_>> {
+        self.replaying
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()
+            .map(|_| ReplayGuard(&self.replaying))
+    }
+
+    pub fn entries(&self) -> Vec<OutboxEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Appends the mutation to the queue and returns its id.
+    fn push(&self, profile: &str, operation: OutboxOperation) -> u64 {
+        self.update(|entries| {
+            let id = entries.iter().map(|entry| entry.id).max().unwrap_or(0) + 1;
+
+            entries.push(OutboxEntry {
+                id,
+                profile: profile.to_string(),
+                operation,
+                queued_at: Local::now().format("%FT%T").to_string(),
+                attempts: 0,
+                error: None,
+            });
+
+            id
+        })
+    }
+
+    /// Removes the given entry, or all of them when not set, and returns how many were removed.
+    fn discard(&self, id: Option<u64>) -> usize {
+        self.update(|entries| {
+            let count = entries.len();
+            entries.retain(|entry| id.is_some_and(|id| id != entry.id));
+
+            count - entries.len()
+        })
+    }
+
+    /// Replays the queued mutations in order, and stops once the server is not reachable so that
+    /// the following ones are not applied before it. Rejected mutations keep their error and are
+    /// skipped by the following replays, unless they are retried by their id.
+    async fn replay(&self, c: &Client, id: Option<u64>) -> Replay {
+        let mut replay = Replay {
+            result: RetryOutboxResult {
+                replayed: 0,
+                failed: 0,
+                pending: 0,
+                offline: false,
+                busy: false,
+            },
+            applied: vec![],
+            rejected: vec![],
+        };
+
+        let Some(_guard) = self.start_replay() else {
+            replay.result.busy = true;
+            replay.result.pending = self.entries().len();
+
+            return replay;
+        };
+
+        let entries = self
+            .entries()
+            .into_iter()
+            .filter(|entry| match id {
+                Some(id) => id == entry.id,
+                None => entry.error.is_none(),
+            })
+            .collect::<Vec<OutboxEntry>>();
+
+        for entry in entries {
+            let Ok(p) = c.profile(Some(entry.profile.as_str())) else {
+                log::warn!(
+                    "Youtrack outbox entry is skipped, since its profile is not configured: {} -> {}",
+                    entry.id,
+                    entry.profile
+                );
+
+                continue;
+            };
+
+            log::debug!(
+                "Youtrack outbox replay: {} -> {:?}",
+                entry.id,
+                entry.operation
+            );
+
+            match entry.operation.apply(c, p).await {
+                Ok(json) => {
+                    self.update(|entries| entries.retain(|e| e.id != entry.id));
+                    replay.result.replayed += 1;
+                    replay.applied.push((entry, json));
+                }
+                Err(err) if err.is_unreachable() => {
+                    replay.result.offline = true;
+
+                    break;
+                }
+                Err(err) => {
+                    log::warn!(
+                        "Youtrack outbox entry has been rejected: {} -> {}",
+                        entry.id,
+                        err
+                    );
+
+                    self.update(|entries| {
+                        if let Some(e) = entries.iter_mut().find(|e| e.id == entry.id) {
+                            e.attempts += 1;
+                            e.error = Some(err.to_string());
+                        }
+                    });
+                    replay.result.failed += 1;
+                    replay.rejected.push(err);
+                }
+            }
+        }
+
+        replay.result.pending = self.entries().len();
+
+        replay
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<OutboxEntry>) -> T) -> T {
+        let mut entries = self.entries.lock().unwrap();
+        let result = f(&mut entries);
+
+        if let Some(ref path) = self.path {
+            let result = path
+                .parent()
+                .map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| {
+                    std::fs::write(
+                        path,
+                        serde_json::to_vec(&*entries).map_err(std::io::Error::other)?,
+                    )
+                });
+
+            if let Err(err) = result {
+                log::warn!(
+                    "Youtrack outbox can not be persisted: {} -> {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+
+        result
+    }
+}
+
+impl Module {
+    /// Queues the mutation and lets the caller know, or returns the original error when the
+    /// outbox is not enabled.
+    pub fn queue(
+        &self,
+        p: &Profile,
+        operation: OutboxOperation,
+        callback: LuaFunction<'_>,
+        err: Error,
+    ) -> Result<NoData, Error> {
+        if !self.outbox.is_enabled() {
+            return Err(err);
+        }
+
+        let id = self.outbox.push(&p.name, operation);
+
+        log::debug!(
+            "Youtrack is not reachable, mutation has been queued: {} -> {}",
+            id,
+            err
+        );
+
+        callback.call::<_, ()>((
+            LuaNil,
+            LuaNil,
+            OutboxMeta {
+                offline: true,
+                queued: true,
+                id,
+            },
+        ))?;
+
+        Ok(NoData)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GetOutbox {}
+
+into_lua!(GetOutbox);
+from_lua!(GetOutbox);
+
+pub fn get_outbox(
+    _: &Lua,
+    m: AppDataRef<'static, Module>,
+    _: Option<GetOutbox>,
+) -> Result<Vec<OutboxEntry>, Error> {
+    Ok(m.outbox.entries())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DiscardOutbox {
+    /// Discards only the given entry, all of them when not set.
+    pub id: Option<u64>,
+}
+
+into_lua!(DiscardOutbox);
+from_lua!(DiscardOutbox);
+
+pub fn discard_outbox(
+    _: &Lua,
+    m: AppDataRef<'static, Module>,
+    options: Option<DiscardOutbox>,
+) -> Result<usize, Error> {
+    let options = options.unwrap_or_default();
+
+    let count = m.outbox.discard(options.id);
+
+    log::debug!("Youtrack outbox discarded: {:?} -> {}", options, count);
+
+    Ok(count)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetryOutboxResult {
+    pub replayed: usize,
+
+    /// Changes that the server has rejected, which are kept with their error until they are
+    /// retried by their id or discarded.
+    pub failed: usize,
+
+    pub pending: usize,
+
+    pub offline: bool,
+
+    /// Nothing has been replayed, since another replay is in progress.
+    pub busy: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RetryOutbox {
+    /// Retries only the given entry, all of them in order when not set except the rejected ones.
+    pub id: Option<u64>,
+}
+
+into_lua!(RetryOutbox);
+from_lua!(RetryOutbox);
+
+pub type RetryOutboxArgs<'lua> = (Option<RetryOutbox>, LuaFunction<'lua>);
+
+/// Outcome of a replay, with the responses of the applied entries to announce them.
+struct Replay {
+    result: RetryOutboxResult,
+    applied: Vec<(OutboxEntry, JsonValue)>,
+    rejected: Vec<Error>,
+}
+
+#[allow(unused_variables)]
+pub async fn retry_outbox(
+    lua: &Lua,
+    m: AppDataRef<'static, Module>,
+    (options, callback): RetryOutboxArgs<'_>,
+) -> Result<NoData, Error> {
+    let options = options.unwrap_or_default();
+
+    let Replay {
+        result,
+        applied,
+        mut rejected,
+    } = m.outbox.replay(&m.client, options.id).await;
+
+    for (entry, json) in applied {
+        if let Ok(p) = m.profile(Some(entry.profile.as_str())) {
+            entry.operation.emit(lua, &m.client, p, &json).await;
+        }
+    }
+
+    log::debug!("Youtrack outbox replayed: {:?} -> {:?}", options, result);
+
+    // a single retried entry gets its rejection, otherwise it is in the result
+    match rejected.pop().filter(|_| options.id.is_some()) {
+        Some(err) => callback.call::<_, ()>((err, lua.to_value(&result)?))?,
+        None => callback.call::<_, ()>((LuaNil, lua.to_value(&result)?))?,
+    }
+
+    Ok(NoData)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Client with the memory backend and an outbox, whose files are in their own directory.
+    fn setup(name: &str) -> (Client, Outbox, PathBuf) {
+        let dir =
+            std::env::temp_dir().join(format!("youtrack-outbox-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let fixture = dir.join("fixture.json");
+        std::fs::write(
+            &fixture,
+            json!({
+                "projects": [{ "id": "0-1", "name": "PRJ", "text": "Project" }],
+                "issues": [{
+                    "id": "2-1",
+                    "text": "PRJ-1",
+                    "summary": "First issue",
+                    "description": null,
+                    "project": { "id": "0-1", "name": "PRJ", "text": "Project" },
+                    "fields": [],
+                    "tags": [],
+                    "comments": [],
+                }],
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let config: Config = serde_json::from_value(json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+            "offline": { "path": dir, "outbox": true },
+            "backend": { "kind": "memory", "fixture": fixture },
+        }))
+        .unwrap();
+
+        let outbox = Outbox::load(&config);
+
+        (Client::new(config).unwrap(), outbox, dir)
+    }
+
+    fn comment(id: &str) -> OutboxOperation {
+        OutboxOperation::AddIssueComment(AddIssueComment {
+            id: id.to_string(),
+            comment: "Queued while offline.".to_string(),
+            profile: None,
+        })
+    }
+
+    #[test]
+    fn queued_entries_are_persisted_and_discarded() {
+        let (_, outbox, dir) = setup("discard");
+
+        assert_eq!(outbox.push("default", comment("PRJ-1")), 1);
+        assert_eq!(outbox.push("default", comment("PRJ-2")), 2);
+
+        let config: Config = serde_json::from_value(json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": [] },
+            "issue": { "fields": [] },
+            "offline": { "path": dir, "outbox": true },
+        }))
+        .unwrap();
+        assert_eq!(Outbox::load(&config).entries().len(), 2);
+
+        assert_eq!(outbox.discard(Some(1)), 1);
+        assert_eq!(outbox.discard(Some(1)), 0);
+        assert_eq!(outbox.entries()[0].id, 2);
+        assert_eq!(outbox.discard(None), 1);
+        assert!(outbox.entries().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_continues_after_rejected_entries() {
+        let (c, outbox, dir) = setup("replay");
+
+        outbox.push("default", comment("PRJ-1"));
+        outbox.push("default", comment("PRJ-404"));
+        outbox.push("removed", comment("PRJ-1"));
+        outbox.push("default", comment("PRJ-1"));
+
+        let replay = crate::RUNTIME.block_on(outbox.replay(&c, None));
+
+        assert_eq!(replay.result.replayed, 2);
+        assert_eq!(replay.result.failed, 1);
+        assert_eq!(replay.result.pending, 2);
+        assert!(!replay.result.offline);
+        assert_eq!(
+            replay.applied.iter().map(|(e, _)| e.id).collect::<Vec<_>>(),
+            vec![1, 4]
+        );
+
+        let entries = outbox.entries();
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(entries[0].attempts, 1);
+        assert!(entries[0].error.is_some());
+        assert_eq!(entries[1].id, 3);
+        assert!(entries[1].error.is_none());
+
+        // rejected entries are only replayed when they are retried by their id
+        let replay = crate::RUNTIME.block_on(outbox.replay(&c, None));
+        assert_eq!((replay.result.replayed, replay.result.failed), (0, 0));
+
+        let replay = crate::RUNTIME.block_on(outbox.replay(&c, Some(2)));
+        assert_eq!((replay.result.replayed, replay.result.failed), (0, 1));
+        assert_eq!(outbox.entries()[0].attempts, 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_is_not_started_twice() {
+        let (c, outbox, dir) = setup("busy");
+
+        outbox.push("default", comment("PRJ-1"));
+
+        let guard = outbox.start_replay();
+        let replay = crate::RUNTIME.block_on(outbox.replay(&c, None));
+
+        assert!(replay.result.busy);
+        assert_eq!((replay.result.replayed, replay.result.pending), (0, 1));
+
+        drop(guard);
+        let replay = crate::RUNTIME.block_on(outbox.replay(&c, None));
+
+        assert!(!replay.result.busy);
+        assert_eq!((replay.result.replayed, replay.result.pending), (1, 0));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[cfg(feature = "lua")]
+use mlua::{AppDataRef, Lua};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::{Config, ConfigIssuesIssue, ConfigIssuesIssues, ConfigProfile};
+use crate::error::Error;
+use crate::macros::{from_lua, into_lua};
+use crate::request::build_client;
+use crate::token::Token;
+#[cfg(feature = "lua")]
+use crate::Module;
+
+/// A configured Youtrack instance with its own client.
+#[derive(Debug)]
+pub struct Profile {
+    pub name: String,
+    pub url: String,
+    pub client: reqwest::Client,
+    pub token: Token,
+    pub api_url: Url,
+    pub hub_api_url: Url,
+    pub issues: ConfigIssuesIssues,
+    pub issue: ConfigIssuesIssue,
+}
+
+impl Profile {
+    pub fn new(config: &Config, name: String, profile: ConfigProfile) -> Result<Self, Error> {
+        let api_url = profile.api_url()?;
+        let hub_api_url = profile.hub_api_url()?;
+        let client = build_client(&config.http)?;
+
+        log::debug!(
+            "Setup the client for profile {} with url: {} and hub url: {}",
+            name,
+            api_url,
+            hub_api_url
+        );
+
+        Ok(Self {
+            name,
+            url: profile.url,
+            client,
+            token: Token::new(profile.token),
+            api_url,
+            hub_api_url,
+            issues: profile.issues.unwrap_or_else(|| config.issues.clone()),
+            issue: profile.issue.unwrap_or_else(|| config.issue.clone()),
+        })
+    }
+}
+
+/// Profiles of the module, and the one that is currently active.
+#[derive(Debug)]
+pub struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+    active: Mutex<String>,
+}
+
+impl Profiles {
+    pub fn new(profiles: BTreeMap<String, Profile>, active: String) -> Result<Self, Error> {
+        if !profiles.contains_key(&active) {
+            return Err(Error::Str(format!("Profile is not configured: {}", active)));
+        }
+
+        Ok(Self {
+            profiles,
+            active: Mutex::new(active),
+        })
+    }
+
+    /// Returns the given profile, or the active one when no name is given.
+    pub fn get(&self, name: Option<&str>) -> Result<&Profile, Error> {
+        let name = name
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| self.active());
+
+        self.profiles
+            .get(&name)
+            .ok_or_else(|| Error::Str(format!("Profile is not configured: {}", name)))
+    }
+
+    pub fn active(&self) -> String {
+        self.active.lock().unwrap().clone()
+    }
+
+    pub fn set_active(&self, name: &str) -> Result<&Profile, Error> {
+        let profile = self.get(Some(name))?;
+
+        *self.active.lock().unwrap() = profile.name.clone();
+
+        Ok(profile)
+    }
+
+    pub fn info(&self, profile: &Profile) -> ProfileInfo {
+        ProfileInfo {
+            name: profile.name.clone(),
+            url: profile.url.clone(),
+            active: profile.name == self.active(),
+        }
+    }
+}
+
+#[cfg(feature = "lua")]
+impl Module {
+    pub fn profile(&self, name: Option<&str>) -> Result<&Profile, Error> {
+        self.client.profile(name)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProfileInfo {
+    pub name: String,
+
+    pub url: String,
+
+    pub active: bool,
+}
+
+into_lua!(ProfileInfo);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GetProfiles {}
+
+into_lua!(GetProfiles);
+from_lua!(GetProfiles);
+
+#[cfg(feature = "lua")]
+pub(crate) fn get_profiles(
+    _: &Lua,
+    m: AppDataRef<'static, Module>,
+    options: Option<GetProfiles>,
+) -> Result<Vec<ProfileInfo>, Error> {
+    log::debug!("Youtrack profiles: {:?}", options.unwrap_or_default());
+
+    Ok(m.client
+        .profiles
+        .profiles
+        .values()
+        .map(|profile| m.client.profiles.info(profile))
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SetProfile {
+    pub name: String,
+}
+
+into_lua!(SetProfile);
+from_lua!(SetProfile);
+
+#[cfg(feature = "lua")]
+pub(crate) fn set_profile(
+    _: &Lua,
+    m: AppDataRef<'static, Module>,
+    options: SetProfile,
+) -> Result<ProfileInfo, Error> {
+    let profile = m.client.profiles.set_active(options.name.as_str())?;
+
+    log::debug!("Switched to profile: {}", profile.name);
+
+    Ok(m.client.profiles.info(profile))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::config::DEFAULT_PROFILE;
+
+    #[test]
+    fn profiles_fall_back_to_the_top_level_fields() {
+        let config: Config = serde_json::from_value(json!({
+            "url": "https://example.com",
+            "token": "perm:token",
+            "issues": { "fields": ["State"] },
+            "issue": { "fields": ["State", "Priority"] },
+            "profiles": {
+                "work": {
+                    "url": "https://work.example.com",
+                    "token": "perm:work",
+                    "issue": { "fields": ["Assignee"] },
+                },
+            },
+        }))
+        .unwrap();
+        let profiles = config.profiles();
+
+        let work = Profile::new(&config, "work".to_string(), profiles["work"].clone()).unwrap();
+        assert_eq!(work.issues.fields, vec!["State"]);
+        assert_eq!(work.issue.fields, vec!["Assignee"]);
+
+        let default = Profile::new(
+            &config,
+            DEFAULT_PROFILE.to_string(),
+            profiles[DEFAULT_PROFILE].clone(),
+        )
+        .unwrap();
+        assert_eq!(default.issue.fields, vec!["State", "Priority"]);
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use chrono::prelude::*;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE, COOKIE, SET_COOKIE,
+};
+use reqwest::{Request, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::config::{ConfigRecorder, RecorderMode};
+use crate::error::Error;
+use crate::inflight::Snapshot;
+use crate::redact::Redactor;
+use crate::RUNTIME;
+
+/// Closes the entries of the recording, which is written before it on every append.
+static FOOTER: &str = "\n  ]\n}}\n";
+
+/// Recording in the format of HTTP archives, only with the fields that are needed for replaying
+/// and inspecting the exchanges.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Har {
+    log: HarLog,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct HarLog {
+    version: String,
+    creator: HarCreator,
+    entries: Vec<HarEntry>,
+}
+
+impl Default for HarLog {
+    fn default() -> Self {
+        Self {
+            version: "1.2".to_string(),
+            creator: HarCreator {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+            },
+            entries: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct HarCreator {
+    name: String,
+    version: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    started_date_time: String,
+    /// Duration of the exchange in milliseconds.
+    time: u128,
+    request: HarRequest,
+    response: HarResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: String,
+    url: String,
+    headers: Vec<HarHeader>,
+    post_data: Option<HarContent>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    status_text: String,
+    headers: Vec<HarHeader>,
+    content: HarContent,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    mime_type: String,
+    text: String,
+}
+
+impl HarRequest {
+    fn new(req: &Request, redactor: &Redactor) -> Self {
+        Self {
+            method: req.method().to_string(),
+            url: req.url().to_string(),
+            headers: har_headers(req.headers(), redactor),
+            post_data: req
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| HarContent {
+                    mime_type: mime_type(req.headers()),
+                    text: redactor.redact(&String::from_utf8_lossy(body)).into_owned(),
+                }),
+        }
+    }
+
+    fn key(&self) -> String {
+        request_key(self.method.as_str(), self.url.as_str())
+    }
+}
+
+impl HarResponse {
+    fn new(snapshot: &Snapshot, redactor: &Redactor) -> Self {
+        Self {
+            status: snapshot.status().as_u16(),
+            status_text: snapshot
+                .status()
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            headers: har_headers(snapshot.headers(), redactor),
+            content: HarContent {
+                mime_type: mime_type(snapshot.headers()),
+                text: redactor
+                    .redact(&String::from_utf8_lossy(snapshot.body()))
+                    .into_owned(),
+            },
+        }
+    }
+
+    fn snapshot(&self) -> Result<Snapshot, Error> {
+        let mut headers = HeaderMap::new();
+
+        for header in self.headers.iter() {
+            headers.insert(
+                HeaderName::from_bytes(header.name.as_bytes())
+                    .map_err(|err| Error::Std(Box::new(err)))?,
+                HeaderValue::from_str(header.value.as_str())
+                    .map_err(|err| Error::Std(Box::new(err)))?,
+            );
+        }
+
+        Ok(Snapshot::new(
+            StatusCode::from_u16(self.status).map_err(|err| Error::Std(Box::new(err)))?,
+            headers,
+            self.content.text.clone().into(),
+        ))
+    }
+}
+
+/// File of the recording, where the entries are appended in front of the footer, so that it is a
+/// complete archive after every exchange without writing it all again.
+#[derive(Debug)]
+struct RecordingFile {
+    path: PathBuf,
+    entries: usize,
+}
+
+impl RecordingFile {
+    fn append(&mut self, entry: &HarEntry) -> std::io::Result<()> {
+        let entry = serde_json::to_string_pretty(entry)
+            .map_err(std::io::Error::other)?
+            .replace('\n', "\n    ");
+
+        let mut file = if self.entries == 0 {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            let mut file = File::create(&self.path)?;
+            let log = HarLog::default();
+
+            write!(
+                file,
+                "{{\n  \"log\": {{\n    \"version\": {},\n    \"creator\": {},\n    \"entries\": [\n    {}",
+                serde_json::to_string(&log.version).map_err(std::io::Error::other)?,
+                serde_json::to_string(&log.creator).map_err(std::io::Error::other)?,
+                entry
+            )?;
+
+            file
+        } else {
+            let mut file = OpenOptions::new().write(true).open(&self.path)?;
+
+            file.seek(SeekFrom::End(-(FOOTER.len() as i64)))?;
+            write!(file, ",\n    {}", entry)?;
+
+            file
+        };
+
+        file.write_all(FOOTER.as_bytes())?;
+        self.entries += 1;
+
+        Ok(())
+    }
+}
+
+/// Records the exchanges with the server into a file, or serves the recorded ones instead of
+/// sending the requests.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    mode: RecorderMode,
+    recording: Option<Arc<Mutex<RecordingFile>>>,
+    /// Masks the secrets in the recording, so that it can be shared.
+    redactor: Redactor,
+    /// Recorded responses per request, the last one is served again when they run out.
+    replay: Mutex<HashMap<String, VecDeque<HarEntry>>>,
+}
+
+impl Recorder {
+    pub fn load(config: &ConfigRecorder, redactor: Redactor) -> Result<Self, Error> {
+        let path = config.path.as_ref().map(PathBuf::from);
+        let mut recorder = Self {
+            mode: config.mode.clone(),
+            recording: path
+                .clone()
+                .filter(|_| config.mode == RecorderMode::Record)
+                .map(|path| Arc::new(Mutex::new(RecordingFile { path, entries: 0 }))),
+            redactor,
+            ..Default::default()
+        };
+
+        if recorder.mode == RecorderMode::Replay {
+            let path = path.as_ref().ok_or_else(|| {
+                Error::Str("Path of the recording is needed for replaying.".to_string())
+            })?;
+            let content = std::fs::read(path).map_err(|err| Error::Std(Box::new(err)))?;
+            let har: Har =
+                serde_json::from_slice(&content).map_err(|err| Error::Std(Box::new(err)))?;
+
+            let mut replay: HashMap<String, VecDeque<HarEntry>> = HashMap::new();
+
+            for entry in har.log.entries {
+                replay
+                    .entry(entry.request.key())
+                    .or_default()
+                    .push_back(entry);
+            }
+
+            log::debug!(
+                "Youtrack recording loaded for replay: {} -> {} requests",
+                path.display(),
+                replay.len()
+            );
+
+            recorder.replay = Mutex::new(replay);
+        }
+
+        Ok(recorder)
+    }
+
+    /// Appends the entry to the recording on a blocking thread, since the requests are driven
+    /// from the main thread of Neovim.
+    async fn record(&self, entry: HarEntry) {
+        let Some(ref recording) = self.recording else {
+            return;
+        };
+        let recording = recording.clone();
+
+        let result = RUNTIME
+            .spawn_blocking(move || {
+                let mut recording = recording.lock().unwrap();
+
+                recording
+                    .append(&entry)
+                    .map_err(|err| format!("{} -> {}", recording.path.display(), err))
+            })
+            .await
+            .unwrap_or_else(|err| Err(err.to_string()));
+
+        if let Err(err) = result {
+            log::warn!("Youtrack recording can not be written: {}", err);
+        }
+    }
+
+    fn replay(&self, req: &Request) -> Result<Snapshot, Error> {
+        let key = request_key(req.method().as_str(), req.url().as_str());
+        let mut replay = self.replay.lock().unwrap();
+
+        let entries = replay
+            .get_mut(&key)
+            .ok_or_else(|| Error::Str(format!("No recorded response for request: {}", key)))?;
+
+        let entry = match entries.len() {
+            1 => entries.front().cloned(),
+            _ => entries.pop_front(),
+        }
+        .ok_or_else(|| Error::Str(format!("No recorded response for request: {}", key)))?;
+
+        log::debug!("Youtrack request served from the recording: {}", key);
+
+        entry.response.snapshot()
+    }
+}
+
+impl Client {
+    /// Sends the request, while it is recorded or replayed as configured.
+    pub async fn execute(&self, req: RequestBuilder) -> Result<Response, Error> {
+        if self.recorder.mode == RecorderMode::Off {
+            return Ok(req.send().await?);
+        }
+
+        let (client, req) = req.build_split();
+        let req = req?;
+
+        if self.recorder.mode == RecorderMode::Replay {
+            return Ok(self.recorder.replay(&req)?.response());
+        }
+
+        let request = HarRequest::new(&req, &self.recorder.redactor);
+        let started = Local::now();
+        let instant = Instant::now();
+
+        let snapshot = Snapshot::read(client.execute(req).await?).await?;
+
+        self.recorder
+            .record(HarEntry {
+                started_date_time: started.to_rfc3339(),
+                time: instant.elapsed().as_millis(),
+                request,
+                response: HarResponse::new(&snapshot, &self.recorder.redactor),
+            })
+            .await;
+
+        Ok(snapshot.response())
+    }
+}
+
+fn request_key(method: &str, url: &str) -> String {
+    format!("{} {}", method, url)
+}
+
+/// Headers without the authorization and the cookies, so that recordings can be shared.
+fn har_headers(headers: &HeaderMap, redactor: &Redactor) -> Vec<HarHeader> {
+    headers
+        .iter()
+        .filter(|(name, _)| ![AUTHORIZATION, COOKIE, SET_COOKIE].contains(name))
+        .map(|(name, value)| HarHeader {
+            name: name.to_string(),
+            value: redactor
+                .redact(&String::from_utf8_lossy(value.as_bytes()))
+                .into_owned(),
+        })
+        .collect()
+}
+
+fn mime_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str) -> HarEntry {
+        HarEntry {
+            started_date_time: "2025-01-01T00:00:00+00:00".to_string(),
+            time: 1,
+            request: HarRequest {
+                method: "GET".to_string(),
+                url: url.to_string(),
+                headers: vec![],
+                post_data: None,
+            },
+            response: HarResponse {
+                status: 200,
+                status_text: "OK".to_string(),
+                headers: vec![],
+                content: HarContent {
+                    mime_type: "application/json".to_string(),
+                    text: "[]".to_string(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn recording_is_complete_after_every_append() {
+        let path = std::env::temp_dir().join(format!("youtrack-{}.har", std::process::id()));
+        let mut recording = RecordingFile {
+            path: path.clone(),
+            entries: 0,
+        };
+
+        for i in 0..3 {
+            recording
+                .append(&entry(&format!("https://example.com/api/issues/{}", i)))
+                .unwrap();
+
+            let har: Har = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            assert_eq!(har.log.entries.len(), i + 1);
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn headers_are_shareable() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer perm:secret"),
+        );
+        headers.insert(SET_COOKIE, HeaderValue::from_static("YTJSESSIONID=secret"));
+        headers.insert(COOKIE, HeaderValue::from_static("YTJSESSIONID=secret"));
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        let headers = har_headers(&headers, &Redactor::default());
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers[0].name, "content-type");
+    }
+}
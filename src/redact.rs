@@ -0,0 +1,192 @@
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::{ConfigRedact, ConfigToken};
+
+static MASK: &str = "***";
+
+/// Tokens that have been resolved from their source like a file or a command, which are only known
+/// once they are needed and are masked by every redactor from then on.
+static RESOLVED: Lazy<RwLock<Vec<String>>> = Lazy::new(|| RwLock::new(vec![]));
+
+static AUTHORIZATION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(bearer|basic)\s+[A-Za-z0-9._~+/=:-]+").unwrap());
+static PERMANENT_TOKEN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\bperm[:-][A-Za-z0-9._~+/=:-]+").unwrap());
+static EMAIL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+// separator after a key in the debug output of the structs, of `serde_json::Value` and in JSON,
+// like `summary: Some("...")`, `"summary": String("...")` or `"summary": "..."`
+static SEPARATOR: &str = r#""?\s*[:=]\s*(?:(?:Some|String)\(\s*)*"#;
+static STRING: &str = r#""(?:[^"\\]|\\.)*""#;
+static ISSUE_TEXT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"(\b(?:summary|description|comment){}){}"#,
+        SEPARATOR, STRING
+    ))
+    .unwrap()
+});
+// `text` is also the readable id of an issue and the value of a field, so only the one of comments
+static COMMENT_TEXT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"((?:\bComment\s*\{{|"\$type"?\s*[:=]\s*(?:String\(\s*)?"IssueComment")[^{{}}]*?\btext{}){}"#,
+        SEPARATOR, STRING
+    ))
+    .unwrap()
+});
+
+/// Masks secrets and, if configured, personal data in the log messages.
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    enabled: bool,
+    emails: bool,
+    issue_text: bool,
+    /// Token values from the configuration, which do not necessarily follow a known format.
+    secrets: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new<'a>(config: &ConfigRedact, tokens: impl Iterator<Item = &'a ConfigToken>) -> Self {
+        Self {
+            enabled: config.enabled,
+            emails: config.emails,
+            issue_text: config.issue_text,
+            secrets: tokens
+                .filter_map(|token| match token {
+                    ConfigToken::Value(value) if !value.is_empty() => Some(value.clone()),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn redact<'a>(&self, message: &'a str) -> Cow<'a, str> {
+        if !self.enabled {
+            return Cow::Borrowed(message);
+        }
+
+        let mut message = Cow::Borrowed(message);
+
+        let resolved = RESOLVED.read().unwrap();
+
+        for secret in self.secrets.iter().chain(resolved.iter()) {
+            if message.contains(secret.as_str()) {
+                message = Cow::Owned(message.replace(secret.as_str(), MASK));
+            }
+        }
+
+        message = replace(message, &AUTHORIZATION, format!("$1 {}", MASK).as_str());
+        message = replace(message, &PERMANENT_TOKEN, MASK);
+
+        if self.emails {
+            message = replace(message, &EMAIL, MASK);
+        }
+
+        if self.issue_text {
+            message = replace(message, &ISSUE_TEXT, format!("$1\"{}\"", MASK).as_str());
+            message = replace(message, &COMMENT_TEXT, format!("$1\"{}\"", MASK).as_str());
+        }
+
+        message
+    }
+}
+
+/// Masks the resolved token in the logs and recordings from now on.
+pub fn register_secret(secret: &str) {
+    let mut resolved = RESOLVED.write().unwrap();
+
+    if !secret.is_empty() && !resolved.iter().any(|s| s == secret) {
+        resolved.push(secret.to_string());
+    }
+}
+
+fn replace<'a>(message: Cow<'a, str>, regex: &Regex, replacement: &str) -> Cow<'a, str> {
+    match regex.replace_all(&message, replacement) {
+        Cow::Borrowed(_) => message,
+        Cow::Owned(replaced) => Cow::Owned(replaced),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(issue_text: bool) -> Redactor {
+        Redactor::new(
+            &ConfigRedact {
+                enabled: true,
+                emails: true,
+                issue_text,
+            },
+            [ConfigToken::Value("custom-secret".to_string())].iter(),
+        )
+    }
+
+    #[test]
+    fn masks_tokens() {
+        let message = "Authorization: Bearer perm:abc.def, token custom-secret, next perm:xyz";
+
+        assert_eq!(
+            redactor(false).redact(message),
+            "Authorization: Bearer ***, token ***, next ***"
+        );
+    }
+
+    #[test]
+    fn masks_emails() {
+        assert_eq!(
+            redactor(false).redact(r#"email: Some("jane@example.com")"#),
+            r#"email: Some("***")"#
+        );
+    }
+
+    #[test]
+    fn masks_issue_text_of_structs() {
+        let message = r#"Issue { id: "2-1", text: "PRJ-1", summary: "Secret plan", description: Some("Details \"quoted\""), comments: Some([Comment { author: "Jane", text: "Private note", created_at: "2025" }]) }"#;
+
+        assert_eq!(
+            redactor(true).redact(message),
+            r#"Issue { id: "2-1", text: "PRJ-1", summary: "***", description: Some("***"), comments: Some([Comment { author: "Jane", text: "***", created_at: "2025" }]) }"#
+        );
+    }
+
+    #[test]
+    fn masks_issue_text_of_json_values() {
+        let message = r#"Object {
+    "$type": String("IssueComment"),
+    "text": String("Private note"),
+}, Object {
+    "summary": String("Secret plan"),
+    "text": String("Open"),
+}"#;
+
+        assert_eq!(
+            redactor(true).redact(message),
+            r#"Object {
+    "$type": String("IssueComment"),
+    "text": String("***"),
+}, Object {
+    "summary": String("***"),
+    "text": String("Open"),
+}"#
+        );
+    }
+
+    #[test]
+    fn masks_issue_text_of_json() {
+        assert_eq!(
+            redactor(true).redact(r#"{"comment":"Private note","text":"PRJ-1"}"#),
+            r#"{"comment":"***","text":"PRJ-1"}"#
+        );
+    }
+
+    #[test]
+    fn keeps_issue_text_unless_configured() {
+        let message = r#"summary: "Secret plan""#;
+
+        assert_eq!(redactor(false).redact(message), message);
+    }
+}
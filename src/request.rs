@@ -0,0 +1,319 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+use chrono::prelude::*;
+use reqwest::{
+    header, Certificate, Identity, NoProxy, Proxy, RequestBuilder, Response, StatusCode,
+};
+
+use crate::client::Client;
+use crate::config::{ConfigHttp, ConfigRetry};
+use crate::error::Error;
+use crate::inflight::{InFlight, Joined, Snapshot};
+use crate::profile::Profile;
+
+static PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+
+/// Builds the HTTP client with the transport options from the configuration.
+pub fn build_client(http: &ConfigHttp) -> Result<reqwest::Client, Error> {
+    let mut builder = reqwest::Client::builder().user_agent("youtrack-nvim");
+
+    if let Some(timeout) = http.connect_timeout {
+        builder = builder.connect_timeout(Duration::from_millis(timeout));
+    }
+
+    if let Some(timeout) = http.timeout {
+        builder = builder.timeout(Duration::from_millis(timeout));
+    }
+
+    if let Some(ref proxy) = http.proxy {
+        builder = builder.proxy(
+            Proxy::all(proxy.as_str())?.no_proxy(NoProxy::from_string(&http.no_proxy.join(","))),
+        );
+    }
+
+    for path in http.ca_certificates.iter() {
+        let pem = read_pem(path)?;
+
+        // a single file can contain a bundle of certificates
+        for cert in pem
+            .split_inclusive(PEM_CERTIFICATE_END)
+            .filter(|cert| cert.contains(PEM_CERTIFICATE_END))
+        {
+            builder = builder.add_root_certificate(Certificate::from_pem(cert.as_bytes())?);
+        }
+
+        log::debug!("Added root certificates from: {}", path);
+    }
+
+    if let Some(ref identity) = http.identity {
+        builder = builder.identity(Identity::from_pkcs8_pem(
+            read_pem(identity.cert.as_str())?.as_bytes(),
+            read_pem(identity.key.as_str())?.as_bytes(),
+        )?);
+
+        log::debug!("Added client identity from: {}", identity.cert);
+    }
+
+    if http.insecure {
+        log::warn!("TLS certificate verification is disabled for the client.");
+
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    Ok(builder.build()?)
+}
+
+fn read_pem(path: &str) -> Result<String, Error> {
+    std::fs::read_to_string(path)
+        .map_err(|err| Error::Str(format!("Can not read the PEM file {}: {}", path, err)))
+}
+
+impl Client {
+    /// Sends the request, while at most the configured amount of requests are in flight.
+    ///
+    /// Reads that are identical to one already in flight wait for its response instead of being
+    /// sent again.
+    pub async fn send(
+        &self,
+        profile: &Profile,
+        req: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<Response, Error> {
+        let Some(key) = InFlight::key(profile, &req).filter(|_| idempotent) else {
+            let _permit = self.inflight.permit().await;
+
+            return self.send_authenticated(profile, req, idempotent).await;
+        };
+
+        let guard = loop {
+            match self.inflight.join(&key) {
+                Joined::Leader(guard) => break guard,
+                Joined::Follower(rx) => match rx.await {
+                    Ok(Ok(snapshot)) => return Ok(snapshot.response()),
+                    Ok(Err(err)) => return Err(Error::Shared(err)),
+                    // the request has been cancelled, one of the waiting callers takes it over
+                    Err(_) => continue,
+                },
+            }
+        };
+
+        let _permit = self.inflight.permit().await;
+
+        let result = match self.send_authenticated(profile, req, idempotent).await {
+            Ok(res) => Snapshot::read(res).await,
+            Err(err) => Err(err),
+        };
+
+        guard.complete(result)
+    }
+
+    /// Sends the request authenticated with the token of the profile.
+    ///
+    /// Token is resolved again from its source when the server responds with unauthorized, and the
+    /// request is repeated once if the token has changed.
+    async fn send_authenticated(
+        &self,
+        profile: &Profile,
+        req: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<Response, Error> {
+        let Some(next) = req.try_clone() else {
+            return self
+                .send_with_retry(
+                    req.header(header::AUTHORIZATION, profile.token.header().await?),
+                    idempotent,
+                )
+                .await;
+        };
+
+        let res = self
+            .send_with_retry(
+                req.header(header::AUTHORIZATION, profile.token.header().await?),
+                idempotent,
+            )
+            .await?;
+
+        if res.status() != StatusCode::UNAUTHORIZED || !profile.token.refresh().await? {
+            return Ok(res);
+        }
+
+        log::debug!(
+            "Youtrack token has been refreshed for profile {}, retrying: {}",
+            profile.name,
+            res.url()
+        );
+
+        self.send_with_retry(
+            next.header(header::AUTHORIZATION, profile.token.header().await?),
+            idempotent,
+        )
+        .await
+    }
+
+    /// Sends the request with the shared client.
+    ///
+    /// Transient failures are retried with exponential backoff as configured, but only when the
+    /// request is known to be idempotent.
+    async fn send_with_retry(
+        &self,
+        req: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<Response, Error> {
+        let retry = &self.config.retry;
+
+        if !retry.enabled || !idempotent {
+            return self.execute(req).await;
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let Some(current) = req.try_clone().filter(|_| attempt <= retry.attempts) else {
+                return self.execute(req).await;
+            };
+
+            let delay = match self.execute(current).await {
+                Ok(res) => match retry_delay(retry, attempt, &res) {
+                    Some(delay) => {
+                        log::debug!(
+                            "Youtrack request failed with {}, retrying in {:?}: {}",
+                            res.status(),
+                            delay,
+                            res.url()
+                        );
+
+                        delay
+                    }
+                    None => return Ok(res),
+                },
+                Err(err) if err.is_offline() => {
+                    let delay = backoff(retry, attempt);
+
+                    log::debug!(
+                        "Youtrack request could not be sent, retrying in {:?}: {}",
+                        delay,
+                        err
+                    );
+
+                    delay
+                }
+                Err(err) => return Err(err),
+            };
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Returns the delay before the next attempt if the response is a transient failure.
+fn retry_delay(retry: &ConfigRetry, attempt: u32, res: &Response) -> Option<Duration> {
+    match res.status() {
+        StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => {}
+        _ => return None,
+    }
+
+    match retry_after(res) {
+        // do not block the caller for longer than what is configured, the server asked for more
+        Some(delay) if delay > Duration::from_millis(retry.max_delay) => None,
+        Some(delay) => Some(delay),
+        None => Some(backoff(retry, attempt)),
+    }
+}
+
+/// Parses the `Retry-After` header, which can either be in seconds or a HTTP date.
+fn retry_after(res: &Response) -> Option<Duration> {
+    let value = res
+        .headers()
+        .get(header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    DateTime::parse_from_rfc2822(value).ok().map(|date| {
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default()
+    })
+}
+
+/// Exponential backoff with equal jitter, so that the delay lands between half and the full step.
+fn backoff(retry: &ConfigRetry, attempt: u32) -> Duration {
+    let step = (retry.delay as f64 * retry.factor.powi(attempt as i32 - 1))
+        .min(retry.max_delay as f64)
+        .max(0.0) as u64;
+    let jitter = RandomState::new().hash_one(attempt) % (step / 2 + 1);
+
+    Duration::from_millis(step - step / 2 + jitter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(retry_after: &str) -> Response {
+        Response::from(
+            http::Response::builder()
+                .status(429)
+                .header(header::RETRY_AFTER, retry_after)
+                .body("")
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn backoff_grows_until_the_max_delay() {
+        let retry = ConfigRetry {
+            enabled: true,
+            attempts: 5,
+            delay: 100,
+            max_delay: 1000,
+            factor: 2.0,
+        };
+
+        [
+            (1, 100),
+            (2, 200),
+            (3, 400),
+            (4, 800),
+            (5, 1000),
+            (10, 1000),
+        ]
+        .iter()
+        .for_each(|(attempt, step)| {
+            let delay = backoff(&retry, *attempt).as_millis() as u64;
+
+            assert!(
+                (step - step / 2..=*step).contains(&delay),
+                "{} -> {}",
+                attempt,
+                delay
+            );
+        });
+    }
+
+    #[test]
+    fn retry_after_is_seconds_or_date() {
+        assert_eq!(retry_after(&response("3")), Some(Duration::from_secs(3)));
+        assert_eq!(
+            retry_after(&response("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Some(Duration::ZERO)
+        );
+
+        let date = (Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let delay = retry_after(&response(&date)).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+
+        assert_eq!(retry_after(&response("soon")), None);
+    }
+}
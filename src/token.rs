@@ -0,0 +1,147 @@
+use std::sync::Mutex;
+
+use reqwest::header::HeaderValue;
+
+use crate::config::ConfigToken;
+use crate::error::Error;
+use crate::redact;
+
+/// Token of a profile, which is resolved from its source when it is first needed.
+#[derive(Debug)]
+pub struct Token {
+    source: ConfigToken,
+    value: Mutex<Option<String>>,
+}
+
+impl Token {
+    pub fn new(source: ConfigToken) -> Self {
+        Self {
+            source,
+            value: Mutex::new(None),
+        }
+    }
+
+    /// Returns the authorization header for the token.
+    pub async fn header(&self) -> Result<HeaderValue, Error> {
+        let cached = self.value.lock().unwrap().clone();
+        let token = match cached {
+            Some(token) => token,
+            None => self.resolve().await?,
+        };
+
+        let mut header =
+            HeaderValue::from_str(format!("Bearer {}", token).as_str()).map_err(|err| {
+                log::error!("Failed to create the authorization header: {}", err);
+                Error::Str("Token is not a valid header value.".to_string())
+            })?;
+        header.set_sensitive(true);
+
+        Ok(header)
+    }
+
+    /// Resolves the token again from its source, and returns whether it has changed.
+    pub async fn refresh(&self) -> Result<bool, Error> {
+        if let ConfigToken::Value(_) = self.source {
+            return Ok(false);
+        }
+
+        let previous = self.value.lock().unwrap().clone();
+
+        Ok(previous.as_ref() != Some(&self.resolve().await?))
+    }
+
+    async fn resolve(&self) -> Result<String, Error> {
+        let token = self.source.resolve().await?;
+
+        redact::register_secret(&token);
+        *self.value.lock().unwrap() = Some(token.clone());
+
+        Ok(token)
+    }
+}
+
+impl ConfigToken {
+    async fn resolve(&self) -> Result<String, Error> {
+        let token = match self {
+            ConfigToken::Value(token) => token.clone(),
+            ConfigToken::Env { env } => std::env::var(env).map_err(|err| {
+                Error::Str(format!(
+                    "Can not read the token from environment variable {}: {}",
+                    env, err
+                ))
+            })?,
+            ConfigToken::File { file } => tokio::fs::read_to_string(file).await.map_err(|err| {
+                Error::Str(format!(
+                    "Can not read the token from file {}: {}",
+                    file, err
+                ))
+            })?,
+            ConfigToken::Command { command } => {
+                let mut cmd = if cfg!(windows) {
+                    let mut cmd = tokio::process::Command::new("cmd");
+                    cmd.arg("/C");
+                    cmd
+                } else {
+                    let mut cmd = tokio::process::Command::new("sh");
+                    cmd.arg("-c");
+                    cmd
+                };
+
+                let output = cmd.arg(command).output().await.map_err(|err| {
+                    Error::Str(format!(
+                        "Can not run the token command {}: {}",
+                        command, err
+                    ))
+                })?;
+
+                if !output.status.success() {
+                    return Err(Error::Str(format!(
+                        "Token command {} failed with {}: {}",
+                        command,
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+
+                String::from_utf8_lossy(&output.stdout).to_string()
+            }
+        };
+
+        let token = token.trim().to_string();
+
+        if token.is_empty() {
+            return Err(Error::Str("Resolved token is empty.".to_string()));
+        }
+
+        log::debug!("Resolved the token from: {}", self);
+
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigRedact;
+    use crate::redact::Redactor;
+
+    #[test]
+    fn resolved_tokens_are_redacted() {
+        let env = format!("YOUTRACK_TEST_TOKEN_{}", std::process::id());
+        std::env::set_var(&env, "  resolved-secret-value\n");
+
+        let token = Token::new(ConfigToken::Env { env: env.clone() });
+        let redactor = Redactor::new(&ConfigRedact::default(), std::iter::empty());
+
+        assert_eq!(
+            redactor.redact("token resolved-secret-value"),
+            "token resolved-secret-value"
+        );
+
+        crate::RUNTIME.block_on(token.header()).unwrap();
+
+        assert_eq!(redactor.redact("token resolved-secret-value"), "token ***");
+
+        std::env::remove_var(&env);
+    }
+}
@@ -1,14 +1,23 @@
 use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
 use std::{
     collections::BTreeMap,
     io::{self},
 };
 
+use crate::config::{ConfigLog, ConfigLogFile};
 use crate::error::Error;
+use crate::executor::{Executor, Notifier};
+use crate::redact::Redactor;
 use log::kv::{Key, Value};
+use log::LevelFilter;
 use mlua::prelude::*;
-use structured_logger::Writer;
+use structured_logger::{Builder, Writer};
 
 #[derive(Debug)]
 pub enum LogLevel {
@@ -45,27 +54,160 @@ impl Display for LogLevel {
     }
 }
 
-pub struct LuaWriter {
-    log: LuaTable<'static>,
-    lua: &'static Lua,
+/// Level filter with overrides per target, where the longest matching target prefix wins.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    level: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
 }
 
-impl LuaWriter {
-    pub fn new(lua: &'static Lua, import: &str) -> Result<Self, Error> {
-        let globals = lua.globals();
-        let require: LuaFunction = globals.get("require")?;
-        let log: LuaTable<'static> = require.call(import)?;
+impl LogFilter {
+    pub fn new(config: &ConfigLog) -> Self {
+        let mut targets = config
+            .targets
+            .iter()
+            .map(|(target, level)| (target.clone(), parse_level(level)))
+            .collect::<Vec<_>>();
+
+        targets.sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+
+        Self {
+            level: parse_level(&config.level),
+            targets,
+        }
+    }
 
-        Ok(Self { lua, log })
+    /// Most verbose level of any target, records above it are not even created.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, std::cmp::max)
     }
 
-    pub fn get(self) -> Box<dyn Writer> {
-        Box::new(self)
+    pub fn enabled(&self, target: &str, level: &str) -> bool {
+        let filter = self
+            .targets
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map_or(self.level, |(_, level)| *level);
+
+        log::Level::from_str(level).is_ok_and(|level| level <= filter)
     }
 }
 
-impl Writer for LuaWriter {
-    fn write_log(&self, value: &BTreeMap<Key, Value>) -> Result<(), io::Error> {
+fn parse_level(level: &str) -> LevelFilter {
+    LevelFilter::from_str(level).unwrap_or(LevelFilter::Info)
+}
+
+struct LogRecord {
+    level: String,
+    target: String,
+    message: String,
+}
+
+/// JSON lines file that is rotated once it exceeds the configured size.
+pub struct FileSink {
+    path: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl FileSink {
+    pub fn open(config: &ConfigLogFile) -> Result<Self, io::Error> {
+        let path = PathBuf::from(&config.path);
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size: config.max_size,
+            max_files: config.max_files,
+            file,
+            size,
+        })
+    }
+
+    fn write(&mut self, value: &BTreeMap<Key, Value>) -> Result<(), io::Error> {
+        let mut line = serde_json::to_vec(value).map_err(io::Error::from)?;
+        line.push(b'\n');
+
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    /// Shifts the previous files, so that `<path>.1` like `youtrack.jsonl.1` is always the most
+    /// recent one and `<path>.<max_files>` the oldest.
+    fn rotate(&mut self) -> Result<(), io::Error> {
+        for index in (1..self.max_files).rev() {
+            match std::fs::rename(self.rotated(index), self.rotated(index + 1)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+
+        if self.max_files > 0 {
+            std::fs::rename(&self.path, self.rotated(1))?;
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+
+        PathBuf::from(path)
+    }
+}
+
+/// Outputs of the installed logger, which are replaced on every setup since the logger itself can
+/// only be installed once.
+pub struct LogOutput {
+    filter: RwLock<LogFilter>,
+    redactor: RwLock<Redactor>,
+    file: Mutex<Option<FileSink>>,
+}
+
+impl LogOutput {
+    pub fn new(filter: LogFilter, redactor: Redactor, file: Option<FileSink>) -> Self {
+        Self {
+            filter: RwLock::new(filter),
+            redactor: RwLock::new(redactor),
+            file: Mutex::new(file),
+        }
+    }
+
+    /// Takes over the filter, redactor and file of the given output.
+    fn replace(&self, output: LogOutput) {
+        *self.filter.write().unwrap() = output.filter.into_inner().unwrap();
+        *self.redactor.write().unwrap() = output.redactor.into_inner().unwrap();
+        *self.file.lock().unwrap() = output.file.into_inner().unwrap();
+    }
+
+    /// Filters and redacts the record and writes it to the file, returns the record to forward
+    /// unless it is filtered out.
+    fn write(&self, value: &BTreeMap<Key, Value>) -> Result<Option<LogRecord>, io::Error> {
         let level = value
             .get("level")
             .map(|v| v.to_string())
@@ -79,14 +221,232 @@ impl Writer for LuaWriter {
             .map(|v| v.to_string())
             .unwrap_or_default();
 
-        self.log
-            .get::<_, LuaTable>("p")
-            .map_err(io::Error::other)?
-            .get::<_, LuaFunction>(LogLevel::Level(level).to_string())
-            .map_err(io::Error::other)?
-            .call(format!("[{}] {}", target, message).into_lua(self.lua))
-            .map_err(io::Error::other)?;
+        if !self.filter.read().unwrap().enabled(&target, &level) {
+            return Ok(None);
+        }
+
+        let message = self.redactor.read().unwrap().redact(&message).into_owned();
+
+        if let Some(ref mut file) = *self.file.lock().unwrap() {
+            let mut value = value.clone();
+            value.insert(Key::from("message"), Value::from(message.as_str()));
+
+            file.write(&value)?;
+        }
+
+        Ok(Some(LogRecord {
+            level,
+            target,
+            message,
+        }))
+    }
+}
+
+/// Installs the logger, which can only be done once. Its own level is the most verbose one, since
+/// it can not be changed afterwards, so the records are only filtered by the max level and the
+/// `LogFilter` of the output that are replaced on every setup.
+pub fn install(writer: Box<dyn Writer>, max_level: LevelFilter) -> Result<(), Error> {
+    Builder::with_level(LevelFilter::Trace.as_str())
+        .with_target_writer("*", writer)
+        .try_init()?;
+    log::set_max_level(max_level);
+
+    Ok(())
+}
+
+/// Forwards the log records to the main thread, since they can be emitted from any thread but
+/// Lua can only be called from the main one.
+pub struct LuaWriter {
+    sender: Sender<LogRecord>,
+    output: Arc<LogOutput>,
+    notifier: Notifier,
+}
+
+/// Receiving side of the log records, which are written to the Lua logger when it is drained.
+pub struct LogForwarder {
+    receiver: Receiver<LogRecord>,
+    output: Arc<LogOutput>,
+    log: LuaTable<'static>,
+}
+
+impl LuaWriter {
+    pub fn new(lua: &'static Lua, import: &str, output: LogOutput) -> Result<Self, Error> {
+        let globals = lua.globals();
+        let require: LuaFunction = globals.get("require")?;
+        let log: LuaTable<'static> = require.call(import)?;
+
+        let (sender, receiver) = mpsc::channel();
+        let output = Arc::new(output);
+
+        lua.set_app_data(LogForwarder {
+            receiver,
+            output: output.clone(),
+            log,
+        });
+
+        Ok(Self {
+            sender,
+            output,
+            notifier: Executor::notifier(lua)?,
+        })
+    }
+
+    pub fn get(self) -> Box<dyn Writer> {
+        Box::new(self)
+    }
+}
+
+impl Writer for LuaWriter {
+    fn write_log(&self, value: &BTreeMap<Key, Value>) -> Result<(), io::Error> {
+        let Some(record) = self.output.write(value)? else {
+            return Ok(());
+        };
+
+        self.sender.send(record).map_err(io::Error::other)?;
+        self.notifier.notify();
+
+        Ok(())
+    }
+}
+
+impl LogForwarder {
+    /// Replaces the outputs of the installed logger.
+    pub fn configure(lua: &Lua, output: LogOutput) {
+        if let Some(forwarder) = lua.app_data_ref::<LogForwarder>() {
+            forwarder.output.replace(output);
+        }
+    }
+
+    /// Writes the pending records to the Lua logger, has to be called on the main thread.
+    pub fn drain(lua: &Lua) -> LuaResult<()> {
+        let Some(forwarder) = lua.app_data_ref::<LogForwarder>() else {
+            return Ok(());
+        };
+
+        let p = forwarder.log.get::<_, LuaTable>("p")?;
+
+        while let Ok(record) = forwarder.receiver.try_recv() {
+            p.get::<_, LuaFunction>(LogLevel::Level(record.level).to_string())?
+                .call::<_, ()>(format!("[{}] {}", record.target, record.message))?;
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_filter_prefers_the_longest_target() {
+        let filter = LogFilter::new(&ConfigLog {
+            level: "warn".to_string(),
+            targets: BTreeMap::from([
+                ("youtrack_nvim".to_string(), "info".to_string()),
+                ("youtrack_nvim::request".to_string(), "trace".to_string()),
+                ("reqwest".to_string(), "off".to_string()),
+            ]),
+            ..ConfigLog::default()
+        });
+
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert!(filter.enabled("youtrack_nvim::request", "TRACE"));
+        assert!(filter.enabled("youtrack_nvim::client", "INFO"));
+        assert!(!filter.enabled("youtrack_nvim::client", "DEBUG"));
+        assert!(!filter.enabled("reqwest::connect", "ERROR"));
+        assert!(filter.enabled("hyper", "WARN"));
+        assert!(!filter.enabled("hyper", "INFO"));
+        assert!(!filter.enabled("hyper", "unknown"));
+    }
+
+    /// Collects the records that pass the output, like the writer that forwards them to Lua.
+    struct TestWriter {
+        output: Arc<LogOutput>,
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Writer for TestWriter {
+        fn write_log(&self, value: &BTreeMap<Key, Value>) -> Result<(), io::Error> {
+            // other tests can log at the same time through the global logger
+            match self.output.write(value)? {
+                Some(record) if record.target == module_path!() => {
+                    self.messages.lock().unwrap().push(record.message)
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn logger_follows_the_reconfigured_level() {
+        let config = |level: &str| ConfigLog {
+            level: level.to_string(),
+            ..ConfigLog::default()
+        };
+        let output = |config: &ConfigLog| {
+            LogOutput::new(
+                LogFilter::new(config),
+                Redactor::new(&config.redact, std::iter::empty()),
+                None,
+            )
+        };
+
+        let warn = config("warn");
+        let shared = Arc::new(output(&warn));
+        let messages = Arc::new(Mutex::new(vec![]));
+
+        install(
+            Box::new(TestWriter {
+                output: shared.clone(),
+                messages: messages.clone(),
+            }),
+            LogFilter::new(&warn).max_level(),
+        )
+        .unwrap();
+
+        log::debug!("dropped");
+        log::warn!("kept");
+
+        let debug = config("debug");
+        shared.replace(output(&debug));
+        log::set_max_level(LogFilter::new(&debug).max_level());
+
+        log::debug!("after setup");
+        log::trace!("dropped");
+
+        assert_eq!(*messages.lock().unwrap(), vec!["kept", "after setup"]);
+    }
+
+    #[test]
+    fn file_sink_rotates_the_files() {
+        let dir = std::env::temp_dir().join(format!("youtrack-log-{}", std::process::id()));
+        let path = dir.join("youtrack.jsonl");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut sink = FileSink::open(&ConfigLogFile {
+            path: path.to_string_lossy().into_owned(),
+            max_size: 32,
+            max_files: 2,
+        })
+        .unwrap();
+        let record =
+            |message: &'static str| BTreeMap::from([(Key::from("message"), Value::from(message))]);
+
+        ["first", "second", "third", "fourth"]
+            .iter()
+            .for_each(|message| sink.write(&record(message)).unwrap());
+
+        // every record exceeds the size together with the previous one
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+
+        assert_eq!(read(path.clone()), "{\"message\":\"fourth\"}\n");
+        assert_eq!(read(sink.rotated(1)), "{\"message\":\"third\"}\n");
+        assert_eq!(read(sink.rotated(2)), "{\"message\":\"second\"}\n");
+        assert!(!sink.rotated(3).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}